@@ -0,0 +1,398 @@
+/**
+ * Maps keyboard, mouse and gamepad input to actions. Gameplay and menus read `ActionState`
+ * instead of raw keys so that controls can be rebound.
+ */
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{config_path, load_config, save_config};
+
+pub struct ActionsPlugin;
+
+pub const BINDINGS_FILE: &str = "bindings.ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    FireUp,
+    FireDown,
+    FireLeft,
+    FireRight,
+    // Fires towards the mouse cursor.
+    FireAtCursor,
+    MenuUp,
+    MenuDown,
+    // Changes the value of the focused slider or toggle.
+    MenuLeft,
+    MenuRight,
+    MenuConfirm,
+    MenuBack,
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    // Short name shown in the controls menu.
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+
+    fn same_device(&self, other: &Binding) -> bool {
+        matches!(
+            (self, other),
+            (Binding::Key(_), Binding::Key(_))
+                | (Binding::Mouse(_), Binding::Mouse(_))
+                | (Binding::Gamepad(_), Binding::Gamepad(_))
+        )
+    }
+}
+
+/*
+* Every binding for every action. Loaded from and saved to the user's bindings.ron.
+*/
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InputBindings(pub BTreeMap<Action, Vec<Binding>>);
+
+impl Default for InputBindings {
+    fn default() -> InputBindings {
+        let mut bindings = BTreeMap::new();
+        bindings.insert(Action::MoveUp, vec![Binding::Key(KeyCode::W)]);
+        bindings.insert(Action::MoveDown, vec![Binding::Key(KeyCode::S)]);
+        bindings.insert(Action::MoveLeft, vec![Binding::Key(KeyCode::A)]);
+        bindings.insert(Action::MoveRight, vec![Binding::Key(KeyCode::D)]);
+        bindings.insert(Action::FireUp, vec![Binding::Key(KeyCode::Up)]);
+        bindings.insert(Action::FireDown, vec![Binding::Key(KeyCode::Down)]);
+        bindings.insert(Action::FireLeft, vec![Binding::Key(KeyCode::Left)]);
+        bindings.insert(Action::FireRight, vec![Binding::Key(KeyCode::Right)]);
+        bindings.insert(
+            Action::FireAtCursor,
+            vec![Binding::Mouse(MouseButton::Left)],
+        );
+        bindings.insert(
+            Action::MenuUp,
+            vec![
+                Binding::Key(KeyCode::W),
+                Binding::Key(KeyCode::Up),
+                Binding::Gamepad(GamepadButtonType::DPadUp),
+            ],
+        );
+        bindings.insert(
+            Action::MenuDown,
+            vec![
+                Binding::Key(KeyCode::S),
+                Binding::Key(KeyCode::Down),
+                Binding::Gamepad(GamepadButtonType::DPadDown),
+            ],
+        );
+        bindings.insert(
+            Action::MenuLeft,
+            vec![
+                Binding::Key(KeyCode::A),
+                Binding::Key(KeyCode::Left),
+                Binding::Gamepad(GamepadButtonType::DPadLeft),
+            ],
+        );
+        bindings.insert(
+            Action::MenuRight,
+            vec![
+                Binding::Key(KeyCode::D),
+                Binding::Key(KeyCode::Right),
+                Binding::Gamepad(GamepadButtonType::DPadRight),
+            ],
+        );
+        bindings.insert(
+            Action::MenuConfirm,
+            vec![
+                Binding::Key(KeyCode::Return),
+                Binding::Gamepad(GamepadButtonType::South),
+            ],
+        );
+        bindings.insert(
+            Action::MenuBack,
+            vec![
+                Binding::Key(KeyCode::Back),
+                Binding::Gamepad(GamepadButtonType::East),
+            ],
+        );
+        bindings.insert(
+            Action::Pause,
+            vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Gamepad(GamepadButtonType::Start),
+            ],
+        );
+        InputBindings(bindings)
+    }
+}
+
+impl InputBindings {
+    // Saved bindings on top of the defaults, so actions added later still get bound.
+    // The defaults are written out on the first run, so there is a file to edit.
+    pub fn load() -> InputBindings {
+        let mut bindings = InputBindings::default();
+        let exists = config_path(BINDINGS_FILE).map_or(false, |path| path.exists());
+        if !exists {
+            bindings.save();
+        } else if let Some(saved) = load_config::<InputBindings>(BINDINGS_FILE) {
+            bindings.0.extend(saved.0);
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        save_config(BINDINGS_FILE, self);
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Replaces the action's binding for the same device (keyboard, mouse or gamepad).
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.0.entry(action).or_insert_with(Vec::new);
+        bindings.retain(|existing| !existing.same_device(&binding));
+        bindings.push(binding);
+    }
+}
+
+/*
+* Actions held, pressed and released this frame plus the analog sticks.
+*/
+#[derive(Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    pub move_axis: Vec2,
+    pub aim_axis: Vec2,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    // Clears the action's presses for the rest of the frame, so that a menu opened by this
+    // press (states can change several times in one frame) doesn't react to it again.
+    pub fn consume(&mut self, action: Action) {
+        self.just_pressed.remove(&action);
+        self.just_released.remove(&action);
+    }
+
+    // Digital move actions, or the left stick when none are held.
+    pub fn movement(&self) -> Vec2 {
+        let direction = self.direction(
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::MoveLeft,
+            Action::MoveRight,
+        );
+        if direction == Vec2::ZERO {
+            self.move_axis
+        } else {
+            direction
+        }
+    }
+
+    // Digital fire actions, not normalized.
+    pub fn fire_direction(&self) -> Vec2 {
+        self.direction(
+            Action::FireUp,
+            Action::FireDown,
+            Action::FireLeft,
+            Action::FireRight,
+        )
+    }
+
+    fn direction(&self, up: Action, down: Action, left: Action, right: Action) -> Vec2 {
+        let mut direction = Vec2::ZERO;
+        if self.pressed(up) {
+            direction.y += 1.;
+        }
+        if self.pressed(down) {
+            direction.y -= 1.;
+        }
+        if self.pressed(left) {
+            direction.x -= 1.;
+        }
+        if self.pressed(right) {
+            direction.x += 1.;
+        }
+        direction
+    }
+}
+
+/*
+* Set to Some(action) to bind the next pressed key, mouse or gamepad button to that action.
+* The controls menu in settings.rs sets it.
+*/
+#[derive(Default)]
+pub struct PendingRebind(pub Option<Action>);
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load())
+            .init_resource::<ActionState>()
+            .init_resource::<PendingRebind>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_action_state.after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                capture_rebind.after(update_action_state),
+            )
+            .add_system(save_bindings);
+    }
+}
+
+fn update_action_state(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<InputBindings>,
+    pending: Res<PendingRebind>,
+    mut action_state: ResMut<ActionState>,
+) {
+    let binding_pressed = |binding: &Binding| match binding {
+        Binding::Key(key) => keyboard_input.pressed(*key),
+        Binding::Mouse(button) => mouse_input.pressed(*button),
+        Binding::Gamepad(button_type) => gamepads
+            .iter()
+            .cloned()
+            .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, *button_type))),
+    };
+    let pressed: HashSet<Action> = bindings
+        .0
+        .iter()
+        .filter(|(_, action_bindings)| {
+            action_bindings
+                .iter()
+                .any(|binding| binding_pressed(binding))
+        })
+        .map(|(action, _)| *action)
+        .collect();
+    let state = &mut *action_state;
+    if pending.0.is_some() {
+        // The input is being captured for a rebind, menus don't see it
+        state.just_pressed.clear();
+        state.just_released.clear();
+    } else {
+        state.just_pressed = pressed.difference(&state.pressed).cloned().collect();
+        state.just_released = state.pressed.difference(&pressed).cloned().collect();
+    }
+    state.pressed = pressed;
+    state.move_axis = stick(
+        &gamepads,
+        &gamepad_axes,
+        GamepadAxisType::LeftStickX,
+        GamepadAxisType::LeftStickY,
+    );
+    state.aim_axis = stick(
+        &gamepads,
+        &gamepad_axes,
+        GamepadAxisType::RightStickX,
+        GamepadAxisType::RightStickY,
+    );
+}
+
+// Every connected gamepad is read as if it was a single controller.
+fn stick(
+    gamepads: &Gamepads,
+    axes: &Axis<GamepadAxis>,
+    x_axis: GamepadAxisType,
+    y_axis: GamepadAxisType,
+) -> Vec2 {
+    let mut value = Vec2::ZERO;
+    for gamepad in gamepads.iter().cloned() {
+        value.x += axes.get(GamepadAxis::new(gamepad, x_axis)).unwrap_or(0.);
+        value.y += axes.get(GamepadAxis::new(gamepad, y_axis)).unwrap_or(0.);
+    }
+    value.clamp_length_max(1.)
+}
+
+/*
+* Binds the first key or button pressed once it is released, so the release doesn't reach
+* the menus either. Input held when the rebind started is ignored.
+*/
+fn capture_rebind(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut pending: ResMut<PendingRebind>,
+    mut bindings: ResMut<InputBindings>,
+    mut held: Local<Option<Binding>>,
+) {
+    let action = match pending.0 {
+        Some(action) => action,
+        None => {
+            *held = None;
+            return;
+        }
+    };
+    let binding = match *held {
+        Some(binding) => binding,
+        None => {
+            *held = keyboard_input
+                .get_just_pressed()
+                .next()
+                .map(|key| Binding::Key(*key))
+                .or_else(|| {
+                    mouse_input
+                        .get_just_pressed()
+                        .next()
+                        .map(|button| Binding::Mouse(*button))
+                })
+                .or_else(|| {
+                    gamepad_buttons
+                        .get_just_pressed()
+                        .next()
+                        .map(|button| Binding::Gamepad(button.button_type))
+                });
+            return;
+        }
+    };
+    let released = match binding {
+        Binding::Key(key) => keyboard_input.just_released(key),
+        Binding::Mouse(button) => mouse_input.just_released(button),
+        Binding::Gamepad(button_type) => gamepad_buttons
+            .get_just_released()
+            .any(|button| button.button_type == button_type),
+    };
+    if released {
+        bindings.rebind(action, binding);
+        pending.0 = None;
+        *held = None;
+    }
+}
+
+fn save_bindings(bindings: Res<InputBindings>) {
+    if bindings.is_changed() && !bindings.is_added() {
+        bindings.save();
+    }
+}
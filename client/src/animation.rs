@@ -1,142 +1,639 @@
 use std::time::Duration;
 
-use bevy::{prelude::*, time::FixedTimestep};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    ecs::system::IntoChainSystem,
+    prelude::*,
+    reflect::TypeUuid,
+    time::FixedTimestep,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
 
 use crate::{
     components::{
-        AnimationTimer, CharacterState, ECharacterAttackState, ECharacterMovementState,
-        ESpriteDirection, SpriteDirection,
+        Animator, CharacterState, Dead, ECharacterAttackState, ECharacterMovementState,
+        ESpriteDirection, SpriteDirection, Velocity,
     },
-    player::{ATTACK_ANIM_SPEED, IDLE_ANIM_SPEED},
-    GameState, TIME_STEP,
+    game::run_if_in_game,
+    health::DamageEvent,
+    TIME_STEP,
 };
 
 pub struct AnimationPlugin;
 
+// Frame events the game reacts to, tagged in the `.anim.ron` files.
+pub const RELEASE_EVENT: &str = "release";
+pub const FOOTSTEP_EVENT: &str = "footstep";
+// Slower than this, in world units per second, a character is standing still.
+pub const WALK_THRESHOLD: f32 = 5.;
+
+/*
+* What a character is doing, each clip is played for one of these.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AnimationState {
+    Idle,
+    Walk,
+    Attack,
+    Hurt,
+    Dead,
+}
+
+impl AnimationState {
+    // A one-shot clip can only be interrupted by a state of at least the same priority.
+    pub fn priority(&self) -> u8 {
+        match self {
+            AnimationState::Idle | AnimationState::Walk => 0,
+            AnimationState::Attack => 1,
+            AnimationState::Hurt => 2,
+            AnimationState::Dead => 3,
+        }
+    }
+
+    // Whose clips are played when a character has none for this state.
+    fn fallback(&self) -> Option<AnimationState> {
+        match self {
+            AnimationState::Walk => Some(AnimationState::Idle),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum LoopMode {
+    Loop,
+    // Plays once and stays on the last frame, lower priority states wait for it to end
+    Once,
+    // Plays forwards then backwards
+    PingPong,
+}
+
+/*
+* Layout of a clip in an `.anim.ron` file, which sits next to the spritesheet it indexes.
+* A clip without a direction is used for every direction that has no clip of its own.
+*/
+#[derive(Deserialize)]
+pub struct AnimationClip {
+    pub name: String,
+    pub state: AnimationState,
+    #[serde(default)]
+    pub direction: Option<ESpriteDirection>,
+    // Index in the texture atlas and how long it is shown, in seconds.
+    pub frames: Vec<(usize, f32)>,
+    pub mode: LoopMode,
+    // Tags sent as an AnimationEvent when the frame at that position in the clip starts.
+    #[serde(default)]
+    pub events: Vec<(usize, String)>,
+    // Movement speed the frame durations are timed for, the clip plays faster or slower
+    // with the actual speed of the character. Clips without one always play at their own pace.
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "8f3d6c21-4b7a-4e59-a0c2-9d1e5f7b3a64"]
+pub struct AnimationClips {
+    pub clips: Vec<AnimationClip>,
+}
+
+impl AnimationClips {
+    // Checks clips from a loader and adds the events the game relies on.
+    pub fn prepare(&mut self) -> Result<(), anyhow::Error> {
+        for clip in &mut self.clips {
+            if clip.frames.is_empty() {
+                anyhow::bail!("clip {} has no frames", clip.name);
+            }
+            // Timers panic on durations that aren't finite and positive
+            if let Some((index, duration)) = clip
+                .frames
+                .iter()
+                .find(|(_, duration)| !duration.is_finite() || *duration <= 0.)
+            {
+                anyhow::bail!(
+                    "clip {} shows tile {} for {} seconds, durations must be above 0",
+                    clip.name,
+                    index,
+                    duration
+                );
+            }
+            if let Some((frame, tag)) = clip
+                .events
+                .iter()
+                .find(|(frame, _)| *frame >= clip.frames.len())
+            {
+                anyhow::bail!(
+                    "clip {} has event {} on frame {} but only {} frames",
+                    clip.name,
+                    tag,
+                    frame,
+                    clip.frames.len()
+                );
+            }
+            // Characters only fire on a release frame, attacks without one release at once
+            if clip.state == AnimationState::Attack
+                && !clip.events.iter().any(|(_, tag)| tag == RELEASE_EVENT)
+            {
+                clip.events.push((0, RELEASE_EVENT.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Index of the clip to play for that state and direction. Clips using tiles past
+     * `tile_count` are skipped, so a four direction sheet falls back to the closest
+     * of its directions even when the file lists diagonal clips.
+     */
+    pub fn find(
+        &self,
+        state: AnimationState,
+        direction: &ESpriteDirection,
+        tile_count: usize,
+    ) -> Option<usize> {
+        let usable = |clip: &AnimationClip| {
+            clip.state == state && clip.frames.iter().all(|(index, _)| *index < tile_count)
+        };
+        std::iter::once(direction)
+            .chain(direction.fallbacks())
+            .find_map(|direction| {
+                self.clips
+                    .iter()
+                    .position(|clip| usable(clip) && clip.direction.as_ref() == Some(direction))
+            })
+            .or_else(|| {
+                self.clips
+                    .iter()
+                    .position(|clip| usable(clip) && clip.direction.is_none())
+            })
+            .or_else(|| {
+                state
+                    .fallback()
+                    .and_then(|fallback| self.find(fallback, direction, tile_count))
+            })
+    }
+}
+
+#[derive(Default)]
+pub struct AnimationClipsLoader;
+
+impl AssetLoader for AnimationClipsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let mut clips: AnimationClips = ron::de::from_bytes(bytes)?;
+            clips.prepare()?;
+            load_context.set_default_asset(LoadedAsset::new(clips));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
+
+/*
+* Sent when a frame with a tag starts playing.
+*/
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub tag: String,
+}
+
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
-                .with_system(change_sprite.before(animate_sprite))
-                .with_system(animate_sprite),
-        );
+        app.add_asset::<AnimationClips>()
+            .init_asset_loader::<AnimationClipsLoader>()
+            .add_event::<AnimationEvent>()
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
+                    .with_system(request_hurt.before(animate_sprite))
+                    .with_system(animate_sprite),
+            );
     }
 }
-pub const DOWN_ATTACK_0: usize = 0;
-pub const DOWN_ATTACK_1: usize = 1;
-pub const LEFT_ATTACK_0: usize = 2;
-pub const LEFT_ATTACK_1: usize = 3;
-pub const RIGHT_ATTACK_0: usize = 4;
-pub const RIGHT_ATTACK_1: usize = 5;
-pub const UP_ATTACK_0: usize = 6;
-pub const UP_ATTACK_1: usize = 7;
-
-//pub const DEAD_0: usize = 8;
-
-pub const DOWN_IDLE_0: usize = 9;
-pub const DOWN_IDLE_1: usize = 10;
-pub const LEFT_IDLE_0: usize = 11;
-pub const LEFT_IDLE_1: usize = 12;
-pub const RIGHT_IDLE_0: usize = 13;
-pub const RIGHT_IDLE_1: usize = 14;
-pub const UP_IDLE_0: usize = 15;
-pub const UP_IDLE_1: usize = 16;
-
-fn change_sprite(
-    mut query: Query<(&mut SpriteDirection, &CharacterState), Changed<CharacterState>>,
+
+fn request_hurt(
+    mut damage_events: EventReader<DamageEvent>,
+    mut query: Query<&mut Animator, Without<Dead>>,
 ) {
-    for (mut sprite_direction, character_state) in &mut query {
-        // Attacking is higher priority over idle
-        if character_state.0 .1 != ECharacterAttackState::IDLE {
-            match character_state.0 .1 {
-                ECharacterAttackState::ATTACK_UP => sprite_direction.0 = ESpriteDirection::UP,
-                ECharacterAttackState::ATTACK_UPRIGHT => {
-                    sprite_direction.0 = ESpriteDirection::RIGHT
-                }
-                ECharacterAttackState::ATTACK_UPLEFT => sprite_direction.0 = ESpriteDirection::LEFT,
-                ECharacterAttackState::ATTACK_RIGHT => sprite_direction.0 = ESpriteDirection::RIGHT,
-                ECharacterAttackState::ATTACK_DOWN => sprite_direction.0 = ESpriteDirection::DOWN,
-                ECharacterAttackState::ATTACK_DOWNRIGHT => {
-                    sprite_direction.0 = ESpriteDirection::RIGHT
-                }
-                ECharacterAttackState::ATTACK_DOWNLEFT => {
-                    sprite_direction.0 = ESpriteDirection::LEFT
-                }
-                ECharacterAttackState::ATTACK_LEFT => sprite_direction.0 = ESpriteDirection::LEFT,
-                ECharacterAttackState::IDLE => { // Should not get here
-                }
-            }
-        } else if character_state.0 .0 != ECharacterMovementState::IDLE {
-            match character_state.0 .0 {
-                ECharacterMovementState::WALK_DOWN => sprite_direction.0 = ESpriteDirection::DOWN,
-                ECharacterMovementState::WALK_LEFT => sprite_direction.0 = ESpriteDirection::LEFT,
-                ECharacterMovementState::WALK_RIGHT => sprite_direction.0 = ESpriteDirection::RIGHT,
-                ECharacterMovementState::WALK_UP => sprite_direction.0 = ESpriteDirection::UP,
-                ECharacterMovementState::IDLE => {
-                    //Should not get here
-                }
-            }
+    for event in damage_events.iter() {
+        if let Ok(mut animator) = query.get_mut(event.target) {
+            animator.request(AnimationState::Hurt);
         }
     }
 }
 
-fn animate_sprite(
+// Direction the character faces, attacking is higher priority over walking.
+fn facing(character_state: &CharacterState) -> Option<ESpriteDirection> {
+    match character_state.0 .1 {
+        ECharacterAttackState::ATTACK_UP => Some(ESpriteDirection::UP),
+        ECharacterAttackState::ATTACK_UPRIGHT => Some(ESpriteDirection::UPRIGHT),
+        ECharacterAttackState::ATTACK_UPLEFT => Some(ESpriteDirection::UPLEFT),
+        ECharacterAttackState::ATTACK_RIGHT => Some(ESpriteDirection::RIGHT),
+        ECharacterAttackState::ATTACK_DOWN => Some(ESpriteDirection::DOWN),
+        ECharacterAttackState::ATTACK_DOWNRIGHT => Some(ESpriteDirection::DOWNRIGHT),
+        ECharacterAttackState::ATTACK_DOWNLEFT => Some(ESpriteDirection::DOWNLEFT),
+        ECharacterAttackState::ATTACK_LEFT => Some(ESpriteDirection::LEFT),
+        ECharacterAttackState::IDLE => match character_state.0 .0 {
+            ECharacterMovementState::WALK_UP => Some(ESpriteDirection::UP),
+            ECharacterMovementState::WALK_UPRIGHT => Some(ESpriteDirection::UPRIGHT),
+            ECharacterMovementState::WALK_UPLEFT => Some(ESpriteDirection::UPLEFT),
+            ECharacterMovementState::WALK_RIGHT => Some(ESpriteDirection::RIGHT),
+            ECharacterMovementState::WALK_DOWN => Some(ESpriteDirection::DOWN),
+            ECharacterMovementState::WALK_DOWNRIGHT => Some(ESpriteDirection::DOWNRIGHT),
+            ECharacterMovementState::WALK_DOWNLEFT => Some(ESpriteDirection::DOWNLEFT),
+            ECharacterMovementState::WALK_LEFT => Some(ESpriteDirection::LEFT),
+            ECharacterMovementState::IDLE => None,
+        },
+    }
+}
+
+/*
+* The animation state machine. Idle, walk and death follow the character state,
+* attack and hurt are requested by gameplay through `Animator::request`.
+* One-shot clips block lower priority states until they end.
+*/
+pub fn animate_sprite(
     time: Res<Time>,
+    animation_clips: Res<Assets<AnimationClips>>,
+    atlases: Res<Assets<TextureAtlas>>,
     mut query: Query<(
-        &mut AnimationTimer,
+        Entity,
+        &mut Animator,
         &mut TextureAtlasSprite,
+        &Handle<TextureAtlas>,
+        &mut SpriteDirection,
         &CharacterState,
-        &SpriteDirection,
+        Option<&Velocity>,
+        Option<&Dead>,
     )>,
+    mut animation_events: EventWriter<AnimationEvent>,
 ) {
-    for (mut timer, mut sprite, character_state, sprite_direction) in &mut query {
-        let mut next_sprite = false;
-        timer.tick(time.delta());
-        if timer.just_finished() {
-            next_sprite = true;
+    for (
+        entity,
+        mut animator,
+        mut sprite,
+        atlas,
+        mut sprite_direction,
+        character_state,
+        velocity,
+        dead,
+    ) in &mut query
+    {
+        if dead.is_none() {
+            if let Some(direction) = facing(character_state) {
+                sprite_direction.0 = direction;
+            }
         }
-        let ( first_index, second_index);
-        // Settings the first and second index for sprite direction.
-        if character_state.0 .1 != ECharacterAttackState::IDLE {
-            match sprite_direction.0 {
-                ESpriteDirection::UP => (first_index, second_index) = (UP_ATTACK_0, UP_ATTACK_1),
-                ESpriteDirection::RIGHT => {
-                    (first_index, second_index) = (RIGHT_ATTACK_0, RIGHT_ATTACK_1)
-                }
-                ESpriteDirection::DOWN => {
-                    (first_index, second_index) = (DOWN_ATTACK_0, DOWN_ATTACK_1)
-                }
-                ESpriteDirection::LEFT => {
-                    (first_index, second_index) = (LEFT_ATTACK_0, LEFT_ATTACK_1)
-                }
+        let clips = match animation_clips.get(&animator.clips) {
+            Some(clips) => clips,
+            None => continue,
+        };
+        let tile_count = match atlases.get(atlas) {
+            Some(atlas) => atlas.len(),
+            None => continue,
+        };
+        let blocking = match animator.clip {
+            Some(clip_index) => {
+                clips.clips[clip_index].mode == LoopMode::Once && !animator.finished
             }
+            None => false,
+        };
+        // Characters pushing against a wall stand still instead of walking in place
+        let moving = match velocity {
+            Some(velocity) => velocity.0.length() >= WALK_THRESHOLD,
+            None => character_state.0 .0 != ECharacterMovementState::IDLE,
+        };
+        let base = if dead.is_some() {
+            AnimationState::Dead
+        } else if moving {
+            AnimationState::Walk
         } else {
-            match sprite_direction.0 {
-                ESpriteDirection::UP => (first_index, second_index) = (UP_IDLE_0, UP_IDLE_1),
-                ESpriteDirection::RIGHT => {
-                    (first_index, second_index) = (RIGHT_IDLE_0, RIGHT_IDLE_1)
+            AnimationState::Idle
+        };
+        let mut frame_started = false;
+        if let Some((next, requested)) = animator.transition(base, blocking) {
+            // States without a clip are skipped, the current clip keeps playing
+            if let Some(clip_index) = clips.find(next, &sprite_direction.0, tile_count) {
+                let restart = requested || animator.clip != Some(clip_index);
+                animator.state = next;
+                if restart {
+                    animator.start(clip_index, &clips.clips[clip_index]);
+                    frame_started = true;
                 }
-                ESpriteDirection::DOWN => (first_index, second_index) = (DOWN_IDLE_0, DOWN_IDLE_1),
-                ESpriteDirection::LEFT => (first_index, second_index) = (LEFT_IDLE_0, LEFT_IDLE_1),
             }
+        } else if let Some(clip_index) = clips.find(animator.state, &sprite_direction.0, tile_count)
+        {
+            if animator.clip.is_none() {
+                animator.start(clip_index, &clips.clips[clip_index]);
+                frame_started = true;
+            } else if animator.clip != Some(clip_index) {
+                // Turning keeps the frame, so walking in a new direction doesn't restart the cycle
+                let frame = animator.frame;
+                animator.start(clip_index, &clips.clips[clip_index]);
+                animator.frame = frame.min(clips.clips[clip_index].frames.len() - 1);
+            }
+        }
+
+        let clip_index = match animator.clip {
+            Some(clip_index) => clip_index,
+            None => continue,
+        };
+        let clip = &clips.clips[clip_index];
+        if !frame_started {
+            let rate = match (clip.speed, velocity) {
+                (Some(speed), Some(velocity)) if speed > 0. => velocity.0.length() / speed,
+                _ => 1.,
+            };
+            animator.timer.tick(time.delta().mul_f32(rate));
+            if animator.timer.just_finished() {
+                frame_started = animator.advance(clip);
+            }
+        }
+        if frame_started {
+            for (_, tag) in clip
+                .events
+                .iter()
+                .filter(|(frame, _)| *frame == animator.frame)
+            {
+                animation_events.send(AnimationEvent {
+                    entity,
+                    tag: tag.clone(),
+                });
+            }
+        }
+        sprite.index = clip.frames[animator.frame].0;
+    }
+}
+
+impl Animator {
+    // Asks the state machine to play that state's clip from the start.
+    pub fn request(&mut self, state: AnimationState) {
+        self.requested = Some(state);
+    }
+
+    /**
+     * State to switch to on this update and whether it was requested, which restarts its clip.
+     * Takes the request. While a one-shot clip blocks, requests need at least its priority
+     * and the character state a higher one.
+     */
+    fn transition(
+        &mut self,
+        base: AnimationState,
+        blocking: bool,
+    ) -> Option<(AnimationState, bool)> {
+        let state = self.state;
+        let requested = self
+            .requested
+            .take()
+            .filter(|requested| !blocking || requested.priority() >= state.priority());
+        if let Some(requested) = requested {
+            return Some((requested, true));
         }
-        // Sets other animations to this current one
-        if sprite.index != first_index && sprite.index != second_index {
-            timer.reset();
-            sprite.index = first_index;
-            // Make sure we are getting the correct animation speed for our timer.
-            if character_state.0.1 != ECharacterAttackState::IDLE {
-                timer.set_duration(Duration::from_secs_f32(ATTACK_ANIM_SPEED));
-            } else {
-                timer.set_duration(Duration::from_secs_f32(IDLE_ANIM_SPEED));
+        let can_change = !blocking || base.priority() > state.priority();
+        (can_change && base != state).then_some((base, false))
+    }
+
+    fn start(&mut self, clip_index: usize, clip: &AnimationClip) {
+        self.clip = Some(clip_index);
+        self.frame = 0;
+        self.reverse = false;
+        self.finished = false;
+        self.timer = Timer::new(Duration::from_secs_f32(clip.frames[0].1), false);
+    }
+
+    // Moves to the next frame, returns false once a one-shot clip stays on its last frame.
+    fn advance(&mut self, clip: &AnimationClip) -> bool {
+        let last = clip.frames.len() - 1;
+        let next = match clip.mode {
+            LoopMode::Loop => (self.frame + 1) % clip.frames.len(),
+            LoopMode::Once => {
+                if self.frame == last {
+                    self.finished = true;
+                    return false;
+                }
+                self.frame + 1
             }
-            //If is the first one and it is ready to go to next
-        } else if sprite.index != first_index && next_sprite {
-            sprite.index = first_index;
-            // If it is the second one ready to go to the first.
-        } else if sprite.index == first_index && next_sprite {
-            sprite.index = second_index;
+            LoopMode::PingPong => {
+                if self.frame == last {
+                    self.reverse = true;
+                } else if self.frame == 0 {
+                    self.reverse = false;
+                }
+                if last == 0 {
+                    0
+                } else if self.reverse {
+                    self.frame - 1
+                } else {
+                    self.frame + 1
+                }
+            }
+        };
+        self.frame = next;
+        self.timer = Timer::new(Duration::from_secs_f32(clip.frames[next].1), false);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(
+        state: AnimationState,
+        direction: Option<ESpriteDirection>,
+        tiles: &[usize],
+        mode: LoopMode,
+    ) -> AnimationClip {
+        AnimationClip {
+            name: format!("{:?} {:?}", state, direction),
+            state,
+            direction,
+            frames: tiles.iter().map(|tile| (*tile, 0.1)).collect(),
+            mode,
+            events: Vec::new(),
+            speed: None,
+        }
+    }
+
+    fn clips() -> AnimationClips {
+        AnimationClips {
+            clips: vec![
+                clip(AnimationState::Idle, None, &[0], LoopMode::Loop),
+                clip(
+                    AnimationState::Idle,
+                    Some(ESpriteDirection::RIGHT),
+                    &[1],
+                    LoopMode::Loop,
+                ),
+                clip(
+                    AnimationState::Idle,
+                    Some(ESpriteDirection::UP),
+                    &[2],
+                    LoopMode::Loop,
+                ),
+                clip(
+                    AnimationState::Idle,
+                    Some(ESpriteDirection::UPRIGHT),
+                    &[30],
+                    LoopMode::Loop,
+                ),
+                clip(AnimationState::Attack, None, &[5, 6], LoopMode::Once),
+            ],
+        }
+    }
+
+    // Plays the clip from its first frame and returns the frames reached by advancing.
+    fn play(clip: &AnimationClip, steps: usize) -> (Animator, Vec<usize>) {
+        let mut animator = Animator::new(Handle::default());
+        animator.start(0, clip);
+        let frames = (0..steps)
+            .map(|_| {
+                animator.advance(clip);
+                animator.frame
+            })
+            .collect();
+        (animator, frames)
+    }
+
+    #[test]
+    fn loop_wraps_around() {
+        let clip = clip(AnimationState::Walk, None, &[0, 1, 2], LoopMode::Loop);
+        let (animator, frames) = play(&clip, 4);
+        assert_eq!(frames, vec![1, 2, 0, 1]);
+        assert!(!animator.finished);
+    }
+
+    #[test]
+    fn once_stays_on_the_last_frame() {
+        let clip = clip(AnimationState::Attack, None, &[0, 1, 2], LoopMode::Once);
+        let mut animator = Animator::new(Handle::default());
+        animator.start(0, &clip);
+        assert!(animator.advance(&clip));
+        assert!(animator.advance(&clip));
+        assert!(!animator.finished);
+        assert!(!animator.advance(&clip));
+        assert_eq!(animator.frame, 2);
+        assert!(animator.finished);
+    }
+
+    #[test]
+    fn ping_pong_reverses_at_both_ends() {
+        let clip = clip(AnimationState::Idle, None, &[0, 1, 2], LoopMode::PingPong);
+        let (_, frames) = play(&clip, 6);
+        assert_eq!(frames, vec![1, 2, 1, 0, 1, 2]);
+
+        let single = self::clip(AnimationState::Idle, None, &[0], LoopMode::PingPong);
+        let (_, frames) = play(&single, 2);
+        assert_eq!(frames, vec![0, 0]);
+    }
+
+    #[test]
+    fn blocking_clip_only_yields_to_equal_or_higher_requests() {
+        let mut animator = Animator::new(Handle::default());
+        animator.state = AnimationState::Hurt;
+
+        animator.request(AnimationState::Attack);
+        assert_eq!(animator.transition(AnimationState::Walk, true), None);
+        // The request is dropped, not kept for when the clip ends
+        assert_eq!(animator.requested, None);
+
+        animator.request(AnimationState::Hurt);
+        assert_eq!(
+            animator.transition(AnimationState::Idle, true),
+            Some((AnimationState::Hurt, true))
+        );
+        assert_eq!(
+            animator.transition(AnimationState::Dead, true),
+            Some((AnimationState::Dead, false))
+        );
+    }
+
+    #[test]
+    fn finished_clip_follows_the_character_state() {
+        let mut animator = Animator::new(Handle::default());
+        animator.state = AnimationState::Attack;
+        assert_eq!(
+            animator.transition(AnimationState::Walk, false),
+            Some((AnimationState::Walk, false))
+        );
+        animator.state = AnimationState::Walk;
+        assert_eq!(animator.transition(AnimationState::Walk, false), None);
+    }
+
+    #[test]
+    fn find_falls_back_to_the_clip_without_direction() {
+        let clips = clips();
+        assert_eq!(
+            clips.find(AnimationState::Idle, &ESpriteDirection::DOWN, 25),
+            Some(0)
+        );
+        assert_eq!(
+            clips.find(AnimationState::Attack, &ESpriteDirection::LEFT, 25),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn prepare_adds_a_release_to_attacks() {
+        let mut clips = clips();
+        clips.prepare().unwrap();
+        assert_eq!(clips.clips[4].events, vec![(0, RELEASE_EVENT.to_string())]);
+        assert!(clips.clips[0].events.is_empty());
+    }
+
+    #[test]
+    fn prepare_rejects_bad_durations_and_events() {
+        for duration in [0., -1., f32::NAN, f32::INFINITY] {
+            let mut clips = clips();
+            clips.clips[0].frames[0].1 = duration;
+            assert!(clips.prepare().is_err());
         }
+        let mut clips = clips();
+        clips.clips[0].events.push((1, FOOTSTEP_EVENT.to_string()));
+        assert!(clips.prepare().is_err());
+    }
+
+    #[test]
+    fn find_falls_back_from_walk_to_idle() {
+        let clips = clips();
+        assert_eq!(
+            clips.find(AnimationState::Walk, &ESpriteDirection::UP, 25),
+            Some(2)
+        );
+        // Only walk falls back
+        assert_eq!(
+            clips.find(AnimationState::Hurt, &ESpriteDirection::UP, 25),
+            None
+        );
+    }
+
+    #[test]
+    fn find_prefers_the_exact_direction() {
+        let clips = clips();
+        assert_eq!(
+            clips.find(AnimationState::Idle, &ESpriteDirection::UPRIGHT, 49),
+            Some(3)
+        );
+        assert_eq!(
+            clips.find(AnimationState::Idle, &ESpriteDirection::RIGHT, 49),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn find_skips_clips_past_the_tile_count() {
+        // A four direction sheet has no diagonal tiles, the closest side is used
+        let clips = clips();
+        assert_eq!(
+            clips.find(AnimationState::Idle, &ESpriteDirection::UPRIGHT, 25),
+            Some(1)
+        );
     }
 }
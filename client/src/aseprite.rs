@@ -0,0 +1,282 @@
+/**
+ * Loads `.aseprite`/`.ase` files as animation clips, with the frames as a texture atlas.
+ * Reference the clips with the file path and the atlas with `#atlas` after it.
+ */
+use asefile::{AnimationDirection, AsepriteFile};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    utils::BoxedFuture,
+};
+use image::RgbaImage;
+
+use crate::{
+    animation::{AnimationClip, AnimationClips, AnimationState, LoopMode},
+    components::ESpriteDirection,
+};
+
+pub struct AsepritePlugin;
+
+pub const ATLAS_LABEL: &str = "atlas";
+pub const IMAGE_LABEL: &str = "image";
+// Tags starting with this mark frame events instead of clips, e.g. `event:release`.
+pub const EVENT_TAG_PREFIX: &str = "event:";
+
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let file = AsepriteFile::read(bytes)?;
+            let frame_size = Vec2::new(file.width() as f32, file.height() as f32);
+
+            let image_handle =
+                load_context.set_labeled_asset(IMAGE_LABEL, LoadedAsset::new(frames_image(&file)));
+            let atlas =
+                TextureAtlas::from_grid(image_handle, frame_size, file.num_frames() as usize, 1);
+            load_context.set_labeled_asset(ATLAS_LABEL, LoadedAsset::new(atlas));
+
+            let mut clips = AnimationClips {
+                clips: clips_from_tags(&file, &load_context.path().display().to_string()),
+            };
+            clips.prepare()?;
+            load_context.set_default_asset(LoadedAsset::new(clips));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite", "ase"]
+    }
+}
+
+impl Plugin for AsepritePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset_loader::<AsepriteLoader>();
+    }
+}
+
+/*
+* Every frame side by side in one row, so the atlas index is the frame number in Aseprite.
+*/
+fn frames_image(file: &AsepriteFile) -> Image {
+    let (width, height) = (file.width() as u32, file.height() as u32);
+    let mut sheet = RgbaImage::new(width * file.num_frames(), height);
+    for frame in 0..file.num_frames() {
+        let image = file.frame(frame).image();
+        image::imageops::replace(&mut sheet, &image, (frame * width) as i64, 0);
+    }
+    Image::new(
+        Extent3d {
+            width: sheet.width(),
+            height: sheet.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        sheet.into_raw(),
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/*
+* A tag named `state` or `state_direction` (e.g. `idle_down`, `attack_upright`, `dead`) becomes
+* a clip over its frames. Attacks, hurts and deaths play once, the rest loop or ping pong
+* like the tag. Event tags add their event to every clip frame they cover.
+*/
+fn clips_from_tags(file: &AsepriteFile, path: &str) -> Vec<AnimationClip> {
+    let tags: Vec<_> = (0..file.num_tags()).map(|index| file.tag(index)).collect();
+    let event_tags: Vec<(&str, u32, u32)> = tags
+        .iter()
+        .filter_map(|tag| {
+            let event = tag.name().strip_prefix(EVENT_TAG_PREFIX)?;
+            Some((event, tag.from_frame(), tag.to_frame()))
+        })
+        .collect();
+
+    let mut clips = Vec::new();
+    for tag in &tags {
+        if tag.name().starts_with(EVENT_TAG_PREFIX) {
+            continue;
+        }
+        let (state, direction) = match parse_tag_name(tag.name()) {
+            Some(parsed) => parsed,
+            None => {
+                warn!(
+                    "{}: ignoring tag {}, it names no animation state",
+                    path,
+                    tag.name()
+                );
+                continue;
+            }
+        };
+        let mut frame_numbers: Vec<u32> = (tag.from_frame()..=tag.to_frame()).collect();
+        if matches!(tag.animation_direction(), AnimationDirection::Reverse) {
+            frame_numbers.reverse();
+        }
+        let frames = frame_numbers
+            .iter()
+            .map(|frame| {
+                let duration = file.frame(*frame).duration() as f32 / 1000.;
+                (*frame as usize, duration)
+            })
+            .collect();
+        let events = frame_numbers
+            .iter()
+            .enumerate()
+            .flat_map(|(position, frame)| {
+                event_tags
+                    .iter()
+                    .filter(move |(_, from, to)| (*from..=*to).contains(frame))
+                    .map(move |(event, _, _)| (position, event.to_string()))
+            })
+            .collect();
+        let mode = match (state, tag.animation_direction()) {
+            (AnimationState::Attack | AnimationState::Hurt | AnimationState::Dead, _) => {
+                LoopMode::Once
+            }
+            (_, AnimationDirection::PingPong) => LoopMode::PingPong,
+            _ => LoopMode::Loop,
+        };
+        clips.push(AnimationClip {
+            name: tag.name().to_string(),
+            state,
+            direction,
+            frames,
+            mode,
+            events,
+            speed: None,
+        });
+    }
+    clips
+}
+
+fn parse_tag_name(name: &str) -> Option<(AnimationState, Option<ESpriteDirection>)> {
+    let name = name.trim().to_lowercase();
+    let (state, direction) = match name.split_once('_') {
+        Some((state, direction)) => (state, Some(direction)),
+        None => (name.as_str(), None),
+    };
+    let state = match state {
+        "idle" => AnimationState::Idle,
+        "walk" => AnimationState::Walk,
+        "attack" => AnimationState::Attack,
+        "hurt" => AnimationState::Hurt,
+        "dead" => AnimationState::Dead,
+        _ => return None,
+    };
+    let direction = match direction {
+        None => None,
+        Some("up") => Some(ESpriteDirection::UP),
+        Some("right") => Some(ESpriteDirection::RIGHT),
+        Some("down") => Some(ESpriteDirection::DOWN),
+        Some("left") => Some(ESpriteDirection::LEFT),
+        Some("upright") => Some(ESpriteDirection::UPRIGHT),
+        Some("upleft") => Some(ESpriteDirection::UPLEFT),
+        Some("downright") => Some(ESpriteDirection::DOWNRIGHT),
+        Some("downleft") => Some(ESpriteDirection::DOWNLEFT),
+        Some(_) => return None,
+    };
+    Some((state, direction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placeholder::{encode_aseprite, AsepriteTag};
+
+    fn tag(name: &str, from: u16, to: u16, direction: u8) -> AsepriteTag {
+        AsepriteTag {
+            name,
+            from,
+            to,
+            direction,
+        }
+    }
+
+    fn clips(durations: &[u16], tags: &[AsepriteTag]) -> Vec<AnimationClip> {
+        let frames: Vec<(RgbaImage, u16)> = durations
+            .iter()
+            .map(|duration| (RgbaImage::new(2, 2), *duration))
+            .collect();
+        let bytes = encode_aseprite(&frames, tags);
+        let file = AsepriteFile::read(&bytes[..]).unwrap();
+        clips_from_tags(&file, "test.aseprite")
+    }
+
+    #[test]
+    fn tag_names_give_the_state_and_direction() {
+        assert_eq!(parse_tag_name("dead"), Some((AnimationState::Dead, None)));
+        assert_eq!(
+            parse_tag_name("idle_down"),
+            Some((AnimationState::Idle, Some(ESpriteDirection::DOWN)))
+        );
+        assert_eq!(
+            parse_tag_name("attack_upright"),
+            Some((AnimationState::Attack, Some(ESpriteDirection::UPRIGHT)))
+        );
+        assert_eq!(
+            parse_tag_name(" Walk_DownLeft "),
+            Some((AnimationState::Walk, Some(ESpriteDirection::DOWNLEFT)))
+        );
+    }
+
+    #[test]
+    fn unknown_tag_names_are_rejected() {
+        for name in ["", "notes", "run_down", "idle_sideways", "event:release"] {
+            assert_eq!(parse_tag_name(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn tags_become_clips_with_their_frames() {
+        let clips = clips(
+            &[100, 100, 200, 50],
+            &[
+                tag("idle_down", 0, 1, 0),
+                tag("notes", 0, 3, 0),
+                tag("walk_left", 0, 2, 2),
+                tag("hurt", 2, 3, 1),
+            ],
+        );
+        let names: Vec<&str> = clips.iter().map(|clip| clip.name.as_str()).collect();
+        assert_eq!(names, vec!["idle_down", "walk_left", "hurt"]);
+
+        assert_eq!(clips[0].state, AnimationState::Idle);
+        assert_eq!(clips[0].direction, Some(ESpriteDirection::DOWN));
+        assert_eq!(clips[0].frames, vec![(0, 0.1), (1, 0.1)]);
+        assert_eq!(clips[0].mode, LoopMode::Loop);
+
+        assert_eq!(clips[1].direction, Some(ESpriteDirection::LEFT));
+        assert_eq!(clips[1].mode, LoopMode::PingPong);
+
+        // Reverse tags play backwards, hurts play once whatever the tag says
+        assert_eq!(clips[2].direction, None);
+        assert_eq!(clips[2].frames, vec![(3, 0.05), (2, 0.2)]);
+        assert_eq!(clips[2].mode, LoopMode::Once);
+    }
+
+    #[test]
+    fn event_tags_mark_the_frames_they_cover() {
+        let clips = clips(
+            &[50, 50, 150, 150],
+            &[
+                tag("attack_right", 0, 1, 0),
+                tag("walk", 2, 3, 0),
+                tag("event:release", 1, 1, 0),
+                tag("event:footstep", 2, 3, 0),
+            ],
+        );
+        assert_eq!(clips.len(), 2);
+        assert_eq!(clips[0].events, vec![(1, "release".to_string())]);
+        assert_eq!(
+            clips[1].events,
+            vec![(0, "footstep".to_string()), (1, "footstep".to_string())]
+        );
+    }
+}
@@ -1,25 +1,63 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
+use crate::{animation::AnimationClips, credits::Credits, weapons::WeaponDefinition};
+
+/*
+* The spritesheet atlases describe the four direction layout from the README. Once loaded, their
+* grid is fitted to the actual size of each sheet (see loading.rs), so diagonal tiles can follow.
+* Aseprite atlases already have one tile per frame.
+*/
 #[derive(AssetCollection)]
 pub struct GameAssets {
     #[asset(path = "game/arenascreen.png")]
     pub arena_background: Handle<Image>,
-    #[asset(path = "game/characters/archer/arrows", collection(typed))]
-    pub archer_arrows: Vec<Handle<Image>>,
-    #[asset(texture_atlas(tile_size_x = 10., tile_size_y = 10., columns = 17, rows = 1))]
+    #[asset(texture_atlas(tile_size_x = 10., tile_size_y = 10., columns = 25, rows = 1))]
     #[asset(path = "game/characters/archer/archer_spritesheet.png")]
     pub archer_tileset: Handle<TextureAtlas>,
-    #[asset(path = "game/characters/archer/arrow_noise.ogg")]
-    pub arrow_noise: Handle<AudioSource>,
+    #[asset(path = "game/characters/archer/archer.anim.ron")]
+    pub archer_animations: Handle<AnimationClips>,
+    #[asset(path = "game/characters/archer/footstep.ogg")]
+    pub footstep: Handle<AudioSource>,
+    #[asset(path = "game/weapons/archer_bow.weapon.ron")]
+    pub archer_bow: Handle<WeaponDefinition>,
+}
+
+#[derive(AssetCollection)]
+pub struct EnemyAssets {
+    #[asset(texture_atlas(tile_size_x = 10., tile_size_y = 10., columns = 25, rows = 1))]
+    #[asset(path = "game/characters/chaser/chaser_spritesheet.png")]
+    pub chaser_tileset: Handle<TextureAtlas>,
+    #[asset(path = "game/characters/chaser/chaser.anim.ron")]
+    pub chaser_animations: Handle<AnimationClips>,
+    #[asset(texture_atlas(tile_size_x = 10., tile_size_y = 10., columns = 25, rows = 1))]
+    #[asset(path = "game/characters/kiting_archer/kiting_archer_spritesheet.png")]
+    pub kiting_archer_tileset: Handle<TextureAtlas>,
+    #[asset(path = "game/characters/kiting_archer/kiting_archer.anim.ron")]
+    pub kiting_archer_animations: Handle<AnimationClips>,
+    // Frames, durations and clips all come from the Aseprite file.
+    #[asset(path = "game/characters/turret/turret.aseprite#atlas")]
+    pub turret_tileset: Handle<TextureAtlas>,
+    #[asset(path = "game/characters/turret/turret.aseprite")]
+    pub turret_animations: Handle<AnimationClips>,
+    #[asset(path = "game/weapons/chaser_bow.weapon.ron")]
+    pub chaser_bow: Handle<WeaponDefinition>,
+    #[asset(path = "game/weapons/kiting_archer_bow.weapon.ron")]
+    pub kiting_archer_bow: Handle<WeaponDefinition>,
+    #[asset(path = "game/weapons/turret_bow.weapon.ron")]
+    pub turret_bow: Handle<WeaponDefinition>,
 }
 
 #[derive(AssetCollection)]
 pub struct MainMenuAssets {
+    #[asset(path = "CREDITS.credits.txt")]
+    pub credits: Handle<Credits>,
     #[asset(path = "mainmenu/alagrad.ttf")]
     pub alagrad: Handle<Font>,
     #[asset(path = "mainmenu/mainmenuscreen.png")]
     pub main_menu_screen: Handle<Image>,
+    #[asset(path = "mainmenu/cursor.png")]
+    pub cursor: Handle<Image>,
     #[asset(path = "mainmenu/ui_button.ogg")]
     pub ui_button: Handle<AudioSource>,
 }
\ No newline at end of file
@@ -0,0 +1,404 @@
+/**
+ * Checks an assets folder against what the game loads, without starting the game.
+ * Usage: `cargo run --bin check_assets -- [assets folder]`, the folder defaults to `assets`.
+ * Prints a report of every file and exits with an error if any of them has a problem.
+ */
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use image::{GenericImageView, RgbaImage};
+use serde::Deserialize;
+
+pub const DEFAULT_ASSETS_FOLDER: &str = "assets";
+pub const WEAPONS_FOLDER: &str = "game/weapons";
+// Must match the texture atlases in assets.rs.
+pub const TILE_SIZE: u32 = 10;
+pub const FRAME_NAMES: [&str; 49] = [
+    "FrontAttack1",
+    "FrontAttack2",
+    "LeftAttack1",
+    "LeftAttack2",
+    "RightAttack1",
+    "RightAttack2",
+    "UpAttack1",
+    "UpAttack2",
+    "Dead",
+    "FrontIdle1",
+    "FrontIdle2",
+    "LeftIdle1",
+    "LeftIdle2",
+    "RightIdle1",
+    "RightIdle2",
+    "UpIdle1",
+    "UpIdle2",
+    "FrontWalk1",
+    "FrontWalk2",
+    "LeftWalk1",
+    "LeftWalk2",
+    "RightWalk1",
+    "RightWalk2",
+    "UpWalk1",
+    "UpWalk2",
+    "FrontLeftAttack1",
+    "FrontLeftAttack2",
+    "FrontRightAttack1",
+    "FrontRightAttack2",
+    "UpLeftAttack1",
+    "UpLeftAttack2",
+    "UpRightAttack1",
+    "UpRightAttack2",
+    "FrontLeftIdle1",
+    "FrontLeftIdle2",
+    "FrontRightIdle1",
+    "FrontRightIdle2",
+    "UpLeftIdle1",
+    "UpLeftIdle2",
+    "UpRightIdle1",
+    "UpRightIdle2",
+    "FrontLeftWalk1",
+    "FrontLeftWalk2",
+    "FrontRightWalk1",
+    "FrontRightWalk2",
+    "UpLeftWalk1",
+    "UpLeftWalk2",
+    "UpRightWalk1",
+    "UpRightWalk2",
+];
+// Sheets without diagonal frames stop after the walks.
+pub const FOUR_DIRECTION_TILES: usize = 25;
+
+/*
+* Every path of the asset collections in assets.rs, keep both in sync.
+*/
+pub const SPRITESHEETS: [&str; 3] = [
+    "game/characters/archer/archer_spritesheet.png",
+    "game/characters/chaser/chaser_spritesheet.png",
+    "game/characters/kiting_archer/kiting_archer_spritesheet.png",
+];
+pub const ASEPRITES: [&str; 1] = ["game/characters/turret/turret.aseprite"];
+pub const IMAGES: [&str; 3] = [
+    "game/arenascreen.png",
+    "mainmenu/mainmenuscreen.png",
+    "mainmenu/cursor.png",
+];
+pub const ANIMATIONS: [&str; 3] = [
+    "game/characters/archer/archer.anim.ron",
+    "game/characters/chaser/chaser.anim.ron",
+    "game/characters/kiting_archer/kiting_archer.anim.ron",
+];
+pub const SOUNDS: [&str; 2] = [
+    "game/characters/archer/footstep.ogg",
+    "mainmenu/ui_button.ogg",
+];
+pub const FONTS: [&str; 1] = ["mainmenu/alagrad.ttf"];
+pub const TEXTS: [&str; 1] = ["CREDITS.credits.txt"];
+
+/*
+* The parts of a `.weapon.ron` file that point to other assets.
+*/
+#[derive(Deserialize)]
+struct WeaponFile {
+    sprite: String,
+    sound: String,
+}
+
+/*
+* The frames of every clip in an `.anim.ron` file.
+*/
+#[derive(Deserialize)]
+struct AnimationFile {
+    clips: Vec<ClipFrames>,
+}
+
+#[derive(Deserialize)]
+struct ClipFrames {
+    name: String,
+    frames: Vec<(usize, f32)>,
+}
+
+/*
+* Problems found for one file. A file without problems is reported as ok.
+*/
+struct FileReport {
+    path: String,
+    problems: Vec<String>,
+}
+
+#[derive(Default)]
+struct Report {
+    files: Vec<FileReport>,
+}
+
+impl Report {
+    fn add(&mut self, path: &str, result: Result<(), Vec<String>>) {
+        // Weapons can share sprites and sounds, each file is reported once
+        if self.files.iter().any(|file| file.path == path) {
+            return;
+        }
+        self.files.push(FileReport {
+            path: path.to_string(),
+            problems: result.err().unwrap_or_default(),
+        });
+    }
+
+    fn problem_count(&self) -> usize {
+        self.files.iter().map(|file| file.problems.len()).sum()
+    }
+
+    fn print(&self) {
+        for file in &self.files {
+            if file.problems.is_empty() {
+                println!("ok     {}", file.path);
+            } else {
+                println!("ERROR  {}", file.path);
+                for problem in &file.problems {
+                    println!("         - {}", problem);
+                }
+            }
+        }
+        let problems = self.problem_count();
+        println!();
+        if problems == 0 {
+            println!("All {} files are valid.", self.files.len());
+        } else {
+            println!(
+                "{} problem(s) in {} of {} files.",
+                problems,
+                self.files
+                    .iter()
+                    .filter(|file| !file.problems.is_empty())
+                    .count(),
+                self.files.len()
+            );
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let folder = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_ASSETS_FOLDER));
+    if !folder.is_dir() {
+        eprintln!("{} is not a folder", folder.display());
+        return ExitCode::FAILURE;
+    }
+    println!("Checking assets in {}\n", folder.display());
+
+    let mut report = Report::default();
+    for path in SPRITESHEETS {
+        report.add(path, check_spritesheet(&folder, path));
+    }
+    for path in ASEPRITES {
+        report.add(path, check_aseprite(&folder, path));
+    }
+    for path in IMAGES {
+        report.add(path, read_image(&folder, path).map(|_| ()));
+    }
+    for path in ANIMATIONS {
+        report.add(path, check_animations(&folder, path));
+    }
+    for path in SOUNDS {
+        report.add(path, check_sound(&folder, path));
+    }
+    for path in FONTS {
+        report.add(path, check_font(&folder, path));
+    }
+    for path in TEXTS {
+        report.add(path, check_text(&folder, path));
+    }
+    check_weapons(&folder, &mut report);
+
+    report.print();
+    if report.problem_count() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn read(folder: &Path, path: &str) -> Result<Vec<u8>, Vec<String>> {
+    fs::read(folder.join(path)).map_err(|error| vec![format!("can't be read: {}", error)])
+}
+
+fn read_image(folder: &Path, path: &str) -> Result<RgbaImage, Vec<String>> {
+    let bytes = read(folder, path)?;
+    match image::load_from_memory(&bytes) {
+        Ok(image) => Ok(image.to_rgba8()),
+        Err(error) => Err(vec![format!("is not a valid image: {}", error)]),
+    }
+}
+
+/*
+* A single row of 10x10 tiles in the order listed in the README, either the 25 tiles of
+* the four direction layout or all 49 with the diagonals.
+* The order itself can't be checked, but an empty tile usually means a frame is missing.
+*/
+fn check_spritesheet(folder: &Path, path: &str) -> Result<(), Vec<String>> {
+    let image = read_image(folder, path)?;
+    let (width, height) = image.dimensions();
+    let tiles = (width / TILE_SIZE) as usize;
+    let layouts = [FOUR_DIRECTION_TILES, FRAME_NAMES.len()];
+    if height != TILE_SIZE || width % TILE_SIZE != 0 || !layouts.contains(&tiles) {
+        let mut problems = vec![format!(
+            "is {}x{}, expected {}x{} or {}x{} ({} or {} tiles of {}x{} in one row)",
+            width,
+            height,
+            TILE_SIZE * FOUR_DIRECTION_TILES as u32,
+            TILE_SIZE,
+            TILE_SIZE * FRAME_NAMES.len() as u32,
+            TILE_SIZE,
+            FOUR_DIRECTION_TILES,
+            FRAME_NAMES.len(),
+            TILE_SIZE,
+            TILE_SIZE
+        )];
+        if width % TILE_SIZE != 0 || height % TILE_SIZE != 0 {
+            problems.push(format!(
+                "the size is not a multiple of {}x{}, tiles must not have padding",
+                TILE_SIZE, TILE_SIZE
+            ));
+        } else {
+            problems.push(format!(
+                "has {} columns and {} rows of tiles",
+                width / TILE_SIZE,
+                height / TILE_SIZE
+            ));
+        }
+        return Err(problems);
+    }
+    let problems: Vec<String> = FRAME_NAMES[..tiles]
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            let tile = image.view(*index as u32 * TILE_SIZE, 0, TILE_SIZE, TILE_SIZE);
+            tile.pixels().all(|(_, _, pixel)| pixel.0[3] == 0)
+        })
+        .map(|(index, name)| format!("tile {} ({}) is empty", index, name))
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+// Every clip needs frames, and they must be tiles of the spritesheet.
+fn check_animations(folder: &Path, path: &str) -> Result<(), Vec<String>> {
+    let bytes = read(folder, path)?;
+    let file: AnimationFile = ron::de::from_bytes(&bytes)
+        .map_err(|error| vec![format!("is not a valid animation file: {}", error)])?;
+    let mut problems = Vec::new();
+    for clip in &file.clips {
+        if clip.frames.is_empty() {
+            problems.push(format!("clip {} has no frames", clip.name));
+        }
+        for (index, duration) in &clip.frames {
+            if *index >= FRAME_NAMES.len() {
+                problems.push(format!(
+                    "clip {} uses tile {}, the layout only has {}",
+                    clip.name,
+                    index,
+                    FRAME_NAMES.len()
+                ));
+            }
+            if *duration <= 0. {
+                problems.push(format!(
+                    "clip {} shows tile {} for {} seconds",
+                    clip.name, index, duration
+                ));
+            }
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+// Clips come from the tags, a file without any has nothing to play.
+fn check_aseprite(folder: &Path, path: &str) -> Result<(), Vec<String>> {
+    let bytes = read(folder, path)?;
+    let file = asefile::AsepriteFile::read(Cursor::new(bytes))
+        .map_err(|error| vec![format!("is not a valid aseprite file: {}", error)])?;
+    if file.num_tags() == 0 {
+        return Err(vec!["has no tags, each clip is a tag".to_string()]);
+    }
+    Ok(())
+}
+
+fn check_sound(folder: &Path, path: &str) -> Result<(), Vec<String>> {
+    let bytes = read(folder, path)?;
+    match rodio::Decoder::new(Cursor::new(bytes)) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(vec![format!("can't be decoded: {}", error)]),
+    }
+}
+
+fn check_font(folder: &Path, path: &str) -> Result<(), Vec<String>> {
+    let bytes = read(folder, path)?;
+    match ab_glyph::FontVec::try_from_vec(bytes) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(vec![format!("is not a valid font: {}", error)]),
+    }
+}
+
+fn check_text(folder: &Path, path: &str) -> Result<(), Vec<String>> {
+    let bytes = read(folder, path)?;
+    match String::from_utf8(bytes) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(vec!["is not valid utf-8 text".to_string()]),
+    }
+}
+
+/*
+* Every weapon file is parsed, then the arrow sprite and firing sound it points to are checked.
+*/
+fn check_weapons(folder: &Path, report: &mut Report) {
+    let weapons_folder = folder.join(WEAPONS_FOLDER);
+    let entries = match fs::read_dir(&weapons_folder) {
+        Ok(entries) => entries,
+        Err(error) => {
+            report.add(
+                WEAPONS_FOLDER,
+                Err(vec![format!("can't be read: {}", error)]),
+            );
+            return;
+        }
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".weapon.ron"))
+        .collect();
+    names.sort();
+    if names.is_empty() {
+        report.add(
+            WEAPONS_FOLDER,
+            Err(vec!["has no .weapon.ron files".to_string()]),
+        );
+    }
+    for name in names {
+        let path = format!("{}/{}", WEAPONS_FOLDER, name);
+        let weapon = read(folder, &path).and_then(|bytes| {
+            ron::de::from_bytes::<WeaponFile>(&bytes)
+                .map_err(|error| vec![format!("is not a valid weapon file: {}", error)])
+        });
+        match weapon {
+            Ok(weapon) => {
+                report.add(&path, Ok(()));
+                report.add(
+                    &weapon.sprite,
+                    read_image(folder, &weapon.sprite).map(|_| ()),
+                );
+                report.add(&weapon.sound, check_sound(folder, &weapon.sound));
+            }
+            Err(problems) => report.add(&path, Err(problems)),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use bevy::prelude::*;
+
+/**
+ * Despawns every entity tagged with `T`. Added to `on_exit` of the state the tag belongs to,
+ * e.g. `cleanup::<OnlyInMainMenu>`.
+ */
+pub fn cleanup<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,13 +1,21 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-use crate::projectiles::ProjectileMask;
+use crate::{
+    animation::{AnimationClips, AnimationState},
+    projectiles::ProjectileMask,
+};
 
+// For characters, how far they actually moved over the last tick, per second.
 #[derive(Component)]
 pub struct Velocity(pub Vec2);
 
 #[derive(Component)]
 pub struct Player;
 
+#[derive(Component)]
+pub struct Enemy;
+
 #[derive(Component)]
 pub struct Position(pub Vec2);
 
@@ -20,12 +28,40 @@ impl Default for ESpriteDirection {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Deserialize)]
 pub enum ESpriteDirection {
     UP,
     RIGHT,
     DOWN,
-    LEFT
+    LEFT,
+    // Only used by sheets that have diagonal frames
+    UPRIGHT,
+    UPLEFT,
+    DOWNRIGHT,
+    DOWNLEFT,
+}
+
+impl ESpriteDirection {
+    // Directions tried in order when a sheet has no frames for this one.
+    pub fn fallbacks(&self) -> &'static [ESpriteDirection] {
+        match self {
+            ESpriteDirection::UPRIGHT => &[ESpriteDirection::RIGHT, ESpriteDirection::UP],
+            ESpriteDirection::UPLEFT => &[ESpriteDirection::LEFT, ESpriteDirection::UP],
+            ESpriteDirection::DOWNRIGHT => &[ESpriteDirection::RIGHT, ESpriteDirection::DOWN],
+            ESpriteDirection::DOWNLEFT => &[ESpriteDirection::LEFT, ESpriteDirection::DOWN],
+            _ => &[],
+        }
+    }
+}
+
+// Normalized direction the character is aiming in.
+#[derive(Component)]
+pub struct Aim(pub Vec2);
+
+impl Default for Aim {
+    fn default() -> Aim {
+        Aim(Vec2::NEG_Y)
+    }
 }
 
 #[derive(Debug, Component)]
@@ -35,11 +71,35 @@ pub struct CharacterState(pub (ECharacterMovementState, ECharacterAttackState));
 pub enum ECharacterMovementState {
     IDLE,
     WALK_UP,
+    WALK_UPRIGHT,
+    WALK_UPLEFT,
     WALK_RIGHT,
     WALK_DOWN,
+    WALK_DOWNRIGHT,
+    WALK_DOWNLEFT,
     WALK_LEFT
 }
 
+impl ECharacterMovementState {
+    // Snaps the direction to the closest of the eight walk directions.
+    pub fn from_direction(direction: Vec2) -> ECharacterMovementState {
+        if direction == Vec2::ZERO {
+            return ECharacterMovementState::IDLE;
+        }
+        let octant = (direction.y.atan2(direction.x) / std::f32::consts::FRAC_PI_4).round() as i32;
+        match octant.rem_euclid(8) {
+            0 => ECharacterMovementState::WALK_RIGHT,
+            1 => ECharacterMovementState::WALK_UPRIGHT,
+            2 => ECharacterMovementState::WALK_UP,
+            3 => ECharacterMovementState::WALK_UPLEFT,
+            4 => ECharacterMovementState::WALK_LEFT,
+            5 => ECharacterMovementState::WALK_DOWNLEFT,
+            6 => ECharacterMovementState::WALK_DOWN,
+            _ => ECharacterMovementState::WALK_DOWNRIGHT,
+        }
+    }
+}
+
 impl Default for ECharacterMovementState {
     fn default() -> ECharacterMovementState {
         ECharacterMovementState::IDLE
@@ -59,20 +119,141 @@ pub enum ECharacterAttackState {
     ATTACK_LEFT,
 }
 
+impl ECharacterAttackState {
+    // Snaps the direction to the closest of the eight attack directions.
+    pub fn from_direction(direction: Vec2) -> ECharacterAttackState {
+        let octant = (direction.y.atan2(direction.x) / std::f32::consts::FRAC_PI_4).round() as i32;
+        match octant.rem_euclid(8) {
+            0 => ECharacterAttackState::ATTACK_RIGHT,
+            1 => ECharacterAttackState::ATTACK_UPRIGHT,
+            2 => ECharacterAttackState::ATTACK_UP,
+            3 => ECharacterAttackState::ATTACK_UPLEFT,
+            4 => ECharacterAttackState::ATTACK_LEFT,
+            5 => ECharacterAttackState::ATTACK_DOWNLEFT,
+            6 => ECharacterAttackState::ATTACK_DOWN,
+            _ => ECharacterAttackState::ATTACK_DOWNRIGHT,
+        }
+    }
+}
+
 impl Default for ECharacterAttackState {
     fn default() -> ECharacterAttackState {
         ECharacterAttackState::IDLE
     }
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub struct AnimationTimer(pub Timer);
+// Plays the clips of a character's `.anim.ron` file, see the state machine in animation.rs.
+#[derive(Component)]
+pub struct Animator {
+    pub clips: Handle<AnimationClips>,
+    pub state: AnimationState,
+    // Transition asked for by gameplay, applied on the next animation update.
+    pub requested: Option<AnimationState>,
+    // Index of the clip playing and of its current frame.
+    pub clip: Option<usize>,
+    pub frame: usize,
+    // Time left on the current frame.
+    pub timer: Timer,
+    // Ping pong clips are on their way back.
+    pub reverse: bool,
+    // One-shot clips reached their last frame.
+    pub finished: bool,
+}
+
+impl Animator {
+    pub fn new(clips: Handle<AnimationClips>) -> Animator {
+        Animator {
+            clips,
+            state: AnimationState::Idle,
+            requested: None,
+            clip: None,
+            frame: 0,
+            timer: Timer::default(),
+            reverse: false,
+            finished: false,
+        }
+    }
+}
 
 #[derive(Component, Deref, DerefMut)]
 pub struct AttackTimer(pub Timer);
 
+// Time between shots for characters that fire on their own.
+#[derive(Component, Deref, DerefMut)]
+pub struct FireCooldown(pub Timer);
+
+// Despawns the entity once the timer finishes.
+#[derive(Component, Deref, DerefMut)]
+pub struct DespawnTimer(pub Timer);
+
+// Axis-aligned hit box, size is in world units (after scaling).
 #[derive(Component)]
-pub struct Collider;
+pub struct Collider(pub Vec2);
 
 #[derive(Component)]
 pub struct Projectile(pub ProjectileMask);
+
+// Entity that fired the projectile.
+#[derive(Component)]
+pub struct Shooter(pub Entity);
+
+// Which side a character is on. Projectiles with the same mask pass through it.
+#[derive(Component)]
+pub struct Team(pub ProjectileMask);
+
+// Damage dealt by a projectile on hit, critical hits already have the multiplier applied.
+#[derive(Component)]
+pub struct Damage {
+    pub amount: f32,
+    pub critical: bool,
+}
+
+#[derive(Debug, Component)]
+pub struct Health {
+    pub max: f32,
+    pub current: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Health {
+        Health { max, current: max }
+    }
+}
+
+// How a character reacts to taking damage, see health.rs.
+#[derive(Component)]
+pub struct HitReaction {
+    // Damage is ignored while it runs, its duration is the invulnerability window.
+    pub invulnerability: Timer,
+    // IN PIXELS PER SECOND, speed the character is pushed at when hit. Decays quickly.
+    pub knockback_speed: f32,
+    pub knockback: Vec2,
+    // IN SECONDS, how long enemies are stunned for. 0 for none.
+    pub stun_time: f32,
+}
+
+impl HitReaction {
+    pub fn new(invulnerability_time: f32, knockback_speed: f32, stun_time: f32) -> HitReaction {
+        let mut invulnerability = Timer::from_seconds(invulnerability_time, false);
+        // Not invulnerable when spawned
+        invulnerability.tick(invulnerability.duration());
+        HitReaction {
+            invulnerability,
+            knockback_speed,
+            knockback: Vec2::ZERO,
+            stun_time,
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerability.finished()
+    }
+}
+
+// Enemies don't move or attack until the timer finishes.
+#[derive(Component, Deref, DerefMut)]
+pub struct HitStun(pub Timer);
+
+// Added once health reaches zero. Dead characters no longer move, attack or animate.
+#[derive(Component)]
+pub struct Dead;
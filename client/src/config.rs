@@ -0,0 +1,52 @@
+/**
+ * User config files, stored in the platform config directory (e.g. ~/.config/2d_shooter).
+ */
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const CONFIG_FOLDER: &str = "2d_shooter";
+
+pub fn config_path(file_name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_FOLDER).join(file_name))
+}
+
+// Returns None when the file is missing or can't be parsed.
+pub fn load_config<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let path = config_path(file_name)?;
+    let contents = fs::read_to_string(&path).ok()?;
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Ignoring invalid config file {}: {}", path.display(), error);
+            None
+        }
+    }
+}
+
+pub fn save_config<T: Serialize>(file_name: &str, value: &T) {
+    let path = match config_path(file_name) {
+        Some(path) => path,
+        None => {
+            warn!("No config directory, {} was not saved", file_name);
+            return;
+        }
+    };
+    let contents = match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => contents,
+        Err(error) => {
+            warn!("Could not serialize {}: {}", file_name, error);
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        if let Err(error) = fs::create_dir_all(parent) {
+            warn!("Could not create {}: {}", parent.display(), error);
+            return;
+        }
+    }
+    if let Err(error) = fs::write(&path, contents) {
+        warn!("Could not write {}: {}", path.display(), error);
+    }
+}
@@ -0,0 +1,269 @@
+/**
+ * CREDITS.credits.txt as an asset, shown as a scrolling list on the credits screen.
+ * The file is read line by line, blank lines are skipped:
+ * - `# Title` starts a section, the lines after it are its entries.
+ * - `Title -- entry` adds the entry to the section with that title, wherever the line is.
+ *   The section is created if it doesn't exist yet.
+ * - Any other line is an entry of the current section, or of an untitled first section.
+ */
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+use crate::{
+    actions::{Action, ActionState},
+    ui::{
+        utils::basic_text,
+        widgets::{DARKCOLOR, LIGHTCOLOR},
+    },
+};
+
+pub struct CreditsPlugin;
+
+// IN PIXELS PER SECOND
+pub const CREDITS_AUTO_SCROLL_SPEED: f32 = 20.;
+pub const CREDITS_MANUAL_SCROLL_SPEED: f32 = 150.;
+// Pixels scrolled per line of a mouse wheel.
+pub const CREDITS_WHEEL_LINE: f32 = 16.;
+pub const CREDITS_VIEW_HEIGHT: f32 = 220.;
+
+pub struct CreditsSection {
+    pub title: String,
+    pub entries: Vec<String>,
+}
+
+/*
+* Sections in the order their title first appears in the file.
+*/
+#[derive(TypeUuid)]
+#[uuid = "2c1f3a47-5d0e-4b8e-9f6a-7b3c2d1e0f59"]
+pub struct Credits {
+    pub sections: Vec<CreditsSection>,
+}
+
+impl Credits {
+    pub fn parse(text: &str) -> Credits {
+        let mut sections: Vec<CreditsSection> = Vec::new();
+        // Index of the section plain lines are added to
+        let mut current: Option<usize> = None;
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(title) = line.strip_prefix('#') {
+                current = Some(section_index(&mut sections, title.trim()));
+            } else if let Some((title, entry)) = line.split_once("--") {
+                let index = section_index(&mut sections, title.trim());
+                sections[index].entries.push(entry.trim().to_string());
+            } else {
+                let index = match current {
+                    Some(index) => index,
+                    None => section_index(&mut sections, ""),
+                };
+                sections[index].entries.push(line.to_string());
+            }
+        }
+        Credits { sections }
+    }
+}
+
+// Index of the section with that title, added at the end if there is none.
+fn section_index(sections: &mut Vec<CreditsSection>, title: &str) -> usize {
+    match sections.iter().position(|section| section.title == title) {
+        Some(index) => index,
+        None => {
+            sections.push(CreditsSection {
+                title: title.to_string(),
+                entries: Vec::new(),
+            });
+            sections.len() - 1
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CreditsLoader;
+
+impl AssetLoader for CreditsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(Credits::parse(text)));
+            Ok(())
+        })
+    }
+
+    // Not plain `txt`, so other text assets aren't read as credits.
+    fn extensions(&self) -> &[&str] {
+        &["credits.txt"]
+    }
+}
+
+/*
+* The scrolled list, `offset` is how far it moved up since the screen opened.
+*/
+#[derive(Component, Default)]
+struct CreditsScroll {
+    offset: f32,
+}
+
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Credits>()
+            .init_asset_loader::<CreditsLoader>()
+            .add_system(scroll_credits);
+    }
+}
+
+/*
+* A clipped view that the credits scroll through, from the bottom to the top.
+*/
+pub fn spawn_credits_list(
+    parent: &mut ChildBuilder,
+    credits: Option<&Credits>,
+    font: Handle<Font>,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Px(CREDITS_VIEW_HEIGHT)),
+                overflow: Overflow::Hidden,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|view| {
+            view.spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(CREDITS_VIEW_HEIGHT),
+                        ..Default::default()
+                    },
+                    flex_direction: FlexDirection::ColumnReverse,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .insert(CreditsScroll::default())
+            .with_children(|list| {
+                let sections = credits.map(|credits| credits.sections.as_slice());
+                for section in sections.unwrap_or(&[]) {
+                    if !section.title.is_empty() {
+                        list.spawn_bundle(basic_text(
+                            &section.title,
+                            16.,
+                            font.clone(),
+                            Some(14.),
+                            Some(4.),
+                            None,
+                            LIGHTCOLOR,
+                        ));
+                    }
+                    for entry in &section.entries {
+                        list.spawn_bundle(basic_text(
+                            entry,
+                            10.,
+                            font.clone(),
+                            Some(2.),
+                            None,
+                            None,
+                            DARKCOLOR,
+                        ));
+                    }
+                }
+            });
+        });
+}
+
+/*
+* Scrolls on its own, menu up/down and the mouse wheel scroll manually.
+* Starts over from the bottom once everything went past the top.
+*/
+fn scroll_credits(
+    actions: Res<ActionState>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut query: Query<(&mut CreditsScroll, &mut Style, &Node)>,
+    time: Res<Time>,
+) {
+    let mut wheel = 0.;
+    for event in wheel_events.iter() {
+        wheel += match event.unit {
+            MouseScrollUnit::Line => event.y * CREDITS_WHEEL_LINE,
+            MouseScrollUnit::Pixel => event.y,
+        };
+    }
+    let mut speed = CREDITS_AUTO_SCROLL_SPEED;
+    if actions.pressed(Action::MenuDown) {
+        speed += CREDITS_MANUAL_SCROLL_SPEED;
+    }
+    if actions.pressed(Action::MenuUp) {
+        speed -= CREDITS_MANUAL_SCROLL_SPEED;
+    }
+    for (mut scroll, mut style, node) in &mut query {
+        let length = CREDITS_VIEW_HEIGHT + node.size.y;
+        // Wheel down moves the list up, like a scrolled page
+        scroll.offset += speed * time.delta_seconds() - wheel;
+        scroll.offset = scroll.offset.rem_euclid(length.max(1.));
+        style.position.top = Val::Px(CREDITS_VIEW_HEIGHT - scroll.offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sections(credits: &Credits) -> Vec<(&str, Vec<&str>)> {
+        credits
+            .sections
+            .iter()
+            .map(|section| {
+                let entries = section.entries.iter().map(String::as_str).collect();
+                (section.title.as_str(), entries)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn titles_start_sections() {
+        let credits = Credits::parse("# Code\nAlice\n\n  Bob  \n# Art\nCarol\n");
+        assert_eq!(
+            sections(&credits),
+            vec![("Code", vec!["Alice", "Bob"]), ("Art", vec!["Carol"])]
+        );
+    }
+
+    #[test]
+    fn dashed_lines_add_to_their_own_section() {
+        let credits = Credits::parse(
+            "# Code\nAlice\nMusic -- Dave\nBob\nCode -- Erin\nFont -- https://example.com/a--b",
+        );
+        assert_eq!(
+            sections(&credits),
+            vec![
+                ("Code", vec!["Alice", "Bob", "Erin"]),
+                ("Music", vec!["Dave"]),
+                ("Font", vec!["https://example.com/a--b"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_before_any_title_go_to_an_untitled_section() {
+        let credits = Credits::parse("Thanks for playing\n# Code\nAlice");
+        assert_eq!(
+            sections(&credits),
+            vec![("", vec!["Thanks for playing"]), ("Code", vec!["Alice"])]
+        );
+        assert!(Credits::parse("\n  \n").sections.is_empty());
+    }
+}
@@ -0,0 +1,457 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::IntoChainSystem, prelude::*, time::FixedTimestep};
+
+use crate::{
+    animation::{animate_sprite, AnimationClips, AnimationEvent, AnimationState, RELEASE_EVENT},
+    assets::EnemyAssets,
+    components::*,
+    game::{run_if_in_game, OnlyInGame, Score, ScoreEvent, MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
+    health::{apply_projectile_damage, DamageEvent, DeathEvent},
+    projectiles::{ArcherArrow, ProjectileMask},
+    settings::{play_sfx, Settings},
+    weapons::WeaponDefinition,
+    TIME_STEP,
+};
+
+pub struct EnemyPlugin;
+
+pub const ENEMY_COLLIDER_SIZE: Vec2 = Vec2::new(12., 16.);
+// IN SECONDS
+pub const CORPSE_DESPAWN_TIME: f32 = 2.0;
+// How long enemies stay in their attack state after firing.
+pub const ENEMY_ATTACK_TIME: f32 = 0.1;
+// Distance band around the preferred range where kiting enemies strafe instead.
+const KITE_TOLERANCE: f32 = 20.;
+
+/*
+* The different kinds of enemies.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub enum EnemyArchetype {
+    // Runs straight at the player and fires up close.
+    Chaser,
+    // Keeps its distance and strafes around the player.
+    KitingArcher,
+    // Never moves, fires quickly from range.
+    Turret,
+}
+
+pub struct EnemyStats {
+    pub speed: f32,
+    pub max_health: f32,
+    // Distance the enemy tries to keep from the player.
+    pub preferred_range: f32,
+    // Distance from which the enemy starts firing.
+    pub attack_range: f32,
+    // Points awarded for killing it.
+    pub score: u32,
+    // IN SECONDS, time after a hit during which it takes no damage.
+    pub invulnerability_time: f32,
+    // IN PIXELS PER SECOND
+    pub knockback_speed: f32,
+    // IN SECONDS, time after a hit during which it doesn't move or attack.
+    pub hit_stun_time: f32,
+}
+
+impl EnemyArchetype {
+    pub fn stats(&self) -> EnemyStats {
+        match self {
+            EnemyArchetype::Chaser => EnemyStats {
+                speed: 70.,
+                max_health: 4.,
+                preferred_range: 20.,
+                attack_range: 90.,
+                score: 10,
+                invulnerability_time: 0.2,
+                knockback_speed: 200.,
+                hit_stun_time: 0.3,
+            },
+            EnemyArchetype::KitingArcher => EnemyStats {
+                speed: 60.,
+                max_health: 4.,
+                preferred_range: 150.,
+                attack_range: 220.,
+                score: 20,
+                invulnerability_time: 0.2,
+                knockback_speed: 200.,
+                hit_stun_time: 0.3,
+            },
+            EnemyArchetype::Turret => EnemyStats {
+                speed: 0.,
+                max_health: 8.,
+                preferred_range: 0.,
+                attack_range: 260.,
+                score: 30,
+                invulnerability_time: 0.2,
+                // Rooted in place
+                knockback_speed: 0.,
+                hit_stun_time: 0.15,
+            },
+        }
+    }
+
+    pub fn tileset(&self, enemy_assets: &EnemyAssets) -> Handle<TextureAtlas> {
+        match self {
+            EnemyArchetype::Chaser => enemy_assets.chaser_tileset.clone(),
+            EnemyArchetype::KitingArcher => enemy_assets.kiting_archer_tileset.clone(),
+            EnemyArchetype::Turret => enemy_assets.turret_tileset.clone(),
+        }
+    }
+
+    pub fn animations(&self, enemy_assets: &EnemyAssets) -> Handle<AnimationClips> {
+        match self {
+            EnemyArchetype::Chaser => enemy_assets.chaser_animations.clone(),
+            EnemyArchetype::KitingArcher => enemy_assets.kiting_archer_animations.clone(),
+            EnemyArchetype::Turret => enemy_assets.turret_animations.clone(),
+        }
+    }
+
+    // Weapon file that sets the fire rate, projectile speed and damage.
+    pub fn weapon(&self, enemy_assets: &EnemyAssets) -> Handle<WeaponDefinition> {
+        match self {
+            EnemyArchetype::Chaser => enemy_assets.chaser_bow.clone(),
+            EnemyArchetype::KitingArcher => enemy_assets.kiting_archer_bow.clone(),
+            EnemyArchetype::Turret => enemy_assets.turret_bow.clone(),
+        }
+    }
+}
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
+                .with_system(
+                    enemy_hit_stun
+                        .after(apply_projectile_damage)
+                        .before(enemy_move)
+                        .before(enemy_attack),
+                )
+                .with_system(recover_from_hit_stun.after(enemy_hit_stun))
+                .with_system(enemy_move)
+                .with_system(enemy_attack.before(animate_sprite))
+                .with_system(enemy_release_arrow.after(animate_sprite))
+                .with_system(enemy_death)
+                .with_system(despawn_corpses),
+        );
+    }
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    archetype: EnemyArchetype,
+    position: Vec2,
+    enemy_assets: &EnemyAssets,
+) -> Entity {
+    let stats = archetype.stats();
+    commands
+        .spawn_bundle(SpriteSheetBundle {
+            texture_atlas: archetype.tileset(enemy_assets),
+            transform: Transform {
+                translation: Vec3 {
+                    x: position.x.clamp(MAP_LEFT_BOUND, MAP_RIGHT_BOUND),
+                    y: position.y.clamp(MAP_DOWN_BOUND, MAP_UP_BOUND),
+                    z: 1.0,
+                },
+                scale: Vec3 {
+                    x: 2.0,
+                    y: 2.0,
+                    z: 2.0,
+                },
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Enemy)
+        .insert(OnlyInGame)
+        .insert(archetype)
+        .insert(Collider(ENEMY_COLLIDER_SIZE))
+        .insert(Team(ProjectileMask::Enemy))
+        .insert(Health::new(stats.max_health))
+        .insert(HitReaction::new(
+            stats.invulnerability_time,
+            stats.knockback_speed,
+            stats.hit_stun_time,
+        ))
+        .insert(SpriteDirection(ESpriteDirection::default()))
+        .insert(CharacterState((
+            ECharacterMovementState::default(),
+            ECharacterAttackState::default(),
+        )))
+        .insert(Animator::new(archetype.animations(enemy_assets)))
+        .insert(Aim::default())
+        .insert(Velocity(Vec2::ZERO))
+        .insert(AttackTimer(Timer::from_seconds(ENEMY_ATTACK_TIME, true)))
+        // Duration is taken from the weapon once it fires.
+        .insert(FireCooldown(Timer::from_seconds(1.0, false)))
+        .id()
+}
+
+fn enemy_move(
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut CharacterState,
+            &mut Velocity,
+            &EnemyArchetype,
+        ),
+        (With<Enemy>, Without<Dead>, Without<HitStun>),
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Dead>, Without<Enemy>)>,
+    time: Res<Time>,
+) {
+    let player_position = player_query
+        .get_single()
+        .map(|player_transform| player_transform.translation.truncate())
+        .ok();
+    for (entity, mut transform, mut character_state, mut velocity, archetype) in &mut query {
+        let stats = archetype.stats();
+        let move_input = match player_position {
+            // Nobody to chase
+            None => Vec2::ZERO,
+            Some(player_position) => {
+                let to_player = player_position - transform.translation.truncate();
+                let distance = to_player.length();
+                let towards = to_player.normalize_or_zero();
+                match archetype {
+                    EnemyArchetype::Chaser => {
+                        if distance > stats.preferred_range {
+                            towards
+                        } else {
+                            Vec2::ZERO
+                        }
+                    }
+                    EnemyArchetype::KitingArcher => {
+                        if distance < stats.preferred_range - KITE_TOLERANCE {
+                            -towards
+                        } else if distance > stats.preferred_range + KITE_TOLERANCE {
+                            towards
+                        } else {
+                            // Circle the player, half of them clockwise.
+                            let strafe = if entity.id() % 2 == 0 { 1. } else { -1. };
+                            towards.perp() * strafe
+                        }
+                    }
+                    EnemyArchetype::Turret => Vec2::ZERO,
+                }
+            }
+        };
+        let movement_state = ECharacterMovementState::from_direction(move_input);
+        if character_state.0 .0 != movement_state {
+            character_state.0 .0 = movement_state;
+        }
+        let mut new_position =
+            transform.translation + move_input.extend(0.) * stats.speed * time.delta_seconds();
+        new_position.x = new_position.x.clamp(MAP_LEFT_BOUND, MAP_RIGHT_BOUND);
+        new_position.y = new_position.y.clamp(MAP_DOWN_BOUND, MAP_UP_BOUND);
+        if time.delta_seconds() > 0. {
+            velocity.0 = (new_position - transform.translation).truncate() / time.delta_seconds();
+        }
+        transform.translation = new_position;
+    }
+}
+
+fn enemy_attack(
+    mut query: Query<
+        (
+            &Transform,
+            &mut AttackTimer,
+            &mut FireCooldown,
+            &mut CharacterState,
+            &mut Aim,
+            &mut Animator,
+            &EnemyArchetype,
+        ),
+        (With<Enemy>, Without<Dead>, Without<HitStun>),
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Dead>, Without<Enemy>)>,
+    time: Res<Time>,
+    enemy_assets: Res<EnemyAssets>,
+    weapons: Res<Assets<WeaponDefinition>>,
+) {
+    let player_transform = player_query.get_single().ok();
+    for (
+        transform,
+        mut timer,
+        mut cooldown,
+        mut character_state,
+        mut aim,
+        mut animator,
+        archetype,
+    ) in &mut query
+    {
+        // Same attack animation handling as the player.
+        if character_state.0 .1 != ECharacterAttackState::IDLE {
+            timer.tick(time.delta());
+        }
+        if timer.just_finished() {
+            character_state.0 .1 = ECharacterAttackState::IDLE;
+            timer.reset();
+        }
+        cooldown.tick(time.delta());
+        let player_transform = match player_transform {
+            Some(player_transform) => player_transform,
+            None => continue,
+        };
+        let stats = archetype.stats();
+        let weapon = match weapons.get(&archetype.weapon(&enemy_assets)) {
+            Some(weapon) => weapon,
+            None => continue,
+        };
+        let to_player = (player_transform.translation - transform.translation).truncate();
+        if cooldown.finished()
+            && character_state.0 .1 == ECharacterAttackState::IDLE
+            && to_player.length() <= stats.attack_range
+        {
+            character_state.0 .1 = ECharacterAttackState::from_direction(to_player);
+            aim.0 = to_player;
+            // The arrow is fired on the release frame of the attack clip.
+            animator.request(AnimationState::Attack);
+            cooldown.set_duration(Duration::from_secs_f32(weapon.fire_cooldown));
+            cooldown.reset();
+        }
+    }
+}
+
+/*
+* Getting hit cancels the attack in progress and freezes the enemy for its stun time.
+* The attack clip is dropped here, so its release frame never plays even when the character
+* has no hurt clip, and stunned enemies are skipped when releasing arrows.
+*/
+fn enemy_hit_stun(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut query: Query<
+        (
+            &HitReaction,
+            &mut CharacterState,
+            &mut AttackTimer,
+            &mut FireCooldown,
+            &mut Velocity,
+            &mut Animator,
+            Option<&mut HitStun>,
+        ),
+        (With<Enemy>, Without<Dead>),
+    >,
+) {
+    for event in damage_events.iter() {
+        let (
+            hit_reaction,
+            mut character_state,
+            mut timer,
+            mut cooldown,
+            mut velocity,
+            mut animator,
+            stun,
+        ) = match query.get_mut(event.target) {
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+        if hit_reaction.stun_time <= 0. {
+            continue;
+        }
+        character_state.0 .0 = ECharacterMovementState::IDLE;
+        character_state.0 .1 = ECharacterAttackState::IDLE;
+        timer.reset();
+        // Also keeps it from attacking again on this tick, before the stun is inserted
+        cooldown.reset();
+        velocity.0 = Vec2::ZERO;
+        // Back to idle on the next animation update, unless a hurt clip is requested
+        if animator.state == AnimationState::Attack {
+            animator.state = AnimationState::Idle;
+            animator.clip = None;
+        }
+        if animator.requested == Some(AnimationState::Attack) {
+            animator.requested = None;
+        }
+        match stun {
+            Some(mut stun) => stun.reset(),
+            None => {
+                commands
+                    .entity(event.target)
+                    .insert(HitStun(Timer::from_seconds(hit_reaction.stun_time, false)));
+            }
+        }
+    }
+}
+
+fn recover_from_hit_stun(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut HitStun)>,
+    time: Res<Time>,
+) {
+    for (entity, mut stun) in &mut query {
+        stun.tick(time.delta());
+        if stun.finished() {
+            commands.entity(entity).remove::<HitStun>();
+        }
+    }
+}
+
+fn enemy_release_arrow(
+    mut commands: Commands,
+    mut animation_events: EventReader<AnimationEvent>,
+    query: Query<
+        (&Transform, &Aim, &EnemyArchetype),
+        (With<Enemy>, Without<Dead>, Without<HitStun>),
+    >,
+    enemy_assets: Res<EnemyAssets>,
+    weapons: Res<Assets<WeaponDefinition>>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+) {
+    for event in animation_events.iter() {
+        if event.tag != RELEASE_EVENT {
+            continue;
+        }
+        let (transform, aim, archetype) = match query.get(event.entity) {
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+        let weapon = match weapons.get(&archetype.weapon(&enemy_assets)) {
+            Some(weapon) => weapon,
+            None => continue,
+        };
+        commands.spawn_bundle(ArcherArrow::new(
+            weapon,
+            &transform.translation,
+            aim.0,
+            event.entity,
+            ProjectileMask::Enemy,
+        ));
+        play_sfx(&audio, weapon.sound.clone(), &settings);
+    }
+}
+
+fn enemy_death(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    query: Query<(Entity, &EnemyArchetype), With<Enemy>>,
+    mut score: ResMut<Score>,
+    mut score_events: EventWriter<ScoreEvent>,
+) {
+    for event in death_events.iter() {
+        if let Ok((entity, archetype)) = query.get(event.entity) {
+            score.0 += archetype.stats().score;
+            score_events.send(ScoreEvent { total: score.0 });
+            commands
+                .entity(entity)
+                .insert(DespawnTimer(Timer::from_seconds(CORPSE_DESPAWN_TIME, false)));
+        }
+    }
+}
+
+fn despawn_corpses(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut DespawnTimer)>,
+    time: Res<Time>,
+) {
+    for (entity, mut timer) in &mut query {
+        timer.tick(time.delta());
+        if timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+/**
+ * Hit feedback: floating damage numbers and a white flash then a tint on the character hit.
+ * Critical hits and killing blows get their own text style. Invulnerable characters blink.
+ */
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    assets::MainMenuAssets, components::HitReaction, game::OnlyInGame, health::DamageEvent,
+    GameState,
+};
+
+pub struct HitFeedbackPlugin;
+
+// Damage numbers, IN SECONDS and PIXELS
+pub const DAMAGE_TEXT_TIME: f32 = 0.8;
+pub const DAMAGE_TEXT_RISE_SPEED: f32 = 30.;
+// Random horizontal offset so numbers from quick hits don't stack on top of each other.
+pub const DAMAGE_TEXT_JITTER: f32 = 6.;
+pub const DAMAGE_TEXT_Z: f32 = 10.;
+pub const DAMAGE_TEXT_SIZE: f32 = 14.;
+pub const CRIT_TEXT_SIZE: f32 = 20.;
+pub const KILL_TEXT_SIZE: f32 = 22.;
+pub const DAMAGE_TEXT_COLOR: Color = Color::rgb(1., 1., 1.);
+pub const CRIT_TEXT_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+pub const KILL_TEXT_COLOR: Color = Color::rgb(0.95, 0.2, 0.15);
+
+// Sprites are drawn fully white for the flash, then fade back from the tint.
+pub const HIT_FLASH_TIME: f32 = 0.05;
+pub const HIT_TINT_TIME: f32 = 0.2;
+// Sprite colors multiply the texture, going over 1 pushes the sprite towards white.
+pub const HIT_FLASH_COLOR: Color = Color::rgb(4., 4., 4.);
+pub const HIT_TINT_COLOR: Color = Color::rgb(1., 0.4, 0.4);
+pub const CRIT_TINT_COLOR: Color = Color::rgb(1., 0.8, 0.3);
+pub const KILL_TINT_COLOR: Color = Color::rgb(0.6, 0.1, 0.1);
+
+// IN SECONDS, time the sprite spends shown then hidden while invulnerable.
+pub const BLINK_INTERVAL: f32 = 0.08;
+
+#[derive(Component)]
+struct DamageText {
+    timer: Timer,
+    color: Color,
+}
+
+#[derive(Component)]
+struct HitFlash {
+    timer: Timer,
+    tint: Color,
+}
+
+impl Plugin for HitFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        // Frame based like the screen shake, it doesn't run while paused either.
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(spawn_hit_feedback)
+                .with_system(float_damage_text)
+                .with_system(flash_hit_sprites.after(spawn_hit_feedback))
+                .with_system(blink_invulnerable),
+        );
+    }
+}
+
+fn spawn_hit_feedback(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    sprite_query: Query<(), With<TextureAtlasSprite>>,
+    mm_assets: Res<MainMenuAssets>,
+) {
+    let mut rng = rand::thread_rng();
+    for event in damage_events.iter() {
+        let (font_size, color, tint) = if event.lethal {
+            (KILL_TEXT_SIZE, KILL_TEXT_COLOR, KILL_TINT_COLOR)
+        } else if event.critical {
+            (CRIT_TEXT_SIZE, CRIT_TEXT_COLOR, CRIT_TINT_COLOR)
+        } else {
+            (DAMAGE_TEXT_SIZE, DAMAGE_TEXT_COLOR, HIT_TINT_COLOR)
+        };
+        let value = if event.critical {
+            format!("{}!", event.amount)
+        } else {
+            event.amount.to_string()
+        };
+        let jitter = rng.gen_range(-DAMAGE_TEXT_JITTER..=DAMAGE_TEXT_JITTER);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::from_section(
+                    value,
+                    TextStyle {
+                        font: mm_assets.alagrad.clone(),
+                        font_size,
+                        color,
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(
+                    (event.point + Vec2::new(jitter, 0.)).extend(DAMAGE_TEXT_Z),
+                ),
+                ..default()
+            })
+            .insert(DamageText {
+                timer: Timer::from_seconds(DAMAGE_TEXT_TIME, false),
+                color,
+            })
+            .insert(OnlyInGame);
+
+        if sprite_query.get(event.target).is_ok() {
+            commands.entity(event.target).insert(HitFlash {
+                timer: Timer::from_seconds(HIT_FLASH_TIME + HIT_TINT_TIME, false),
+                tint,
+            });
+        }
+    }
+}
+
+fn float_damage_text(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut DamageText, &mut Transform, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (entity, mut damage_text, mut transform, mut text) in &mut query {
+        damage_text.timer.tick(time.delta());
+        if damage_text.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation.y += DAMAGE_TEXT_RISE_SPEED * time.delta_seconds();
+        // Fully visible for the first half, then fades out
+        let alpha = (2. * (1. - damage_text.timer.percent())).min(1.);
+        let mut color = damage_text.color;
+        color.set_a(alpha);
+        text.sections[0].style.color = color;
+    }
+}
+
+fn flash_hit_sprites(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut HitFlash, &mut TextureAtlasSprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut flash, mut sprite) in &mut query {
+        flash.timer.tick(time.delta());
+        let elapsed = flash.timer.elapsed_secs();
+        sprite.color = if flash.timer.finished() {
+            commands.entity(entity).remove::<HitFlash>();
+            Color::WHITE
+        } else if elapsed < HIT_FLASH_TIME {
+            HIT_FLASH_COLOR
+        } else {
+            // Tint fades back to the normal sprite color
+            let t = (elapsed - HIT_FLASH_TIME) / HIT_TINT_TIME;
+            let [r, g, b, a] = flash.tint.as_rgba_f32();
+            Color::rgba(
+                r + (1. - r) * t,
+                g + (1. - g) * t,
+                b + (1. - b) * t,
+                a + (1. - a) * t,
+            )
+        };
+    }
+}
+
+fn blink_invulnerable(mut query: Query<(&HitReaction, &mut Visibility)>) {
+    for (hit_reaction, mut visibility) in &mut query {
+        let is_visible = !hit_reaction.is_invulnerable()
+            || (hit_reaction.invulnerability.elapsed_secs() / BLINK_INTERVAL) as u32 % 2 == 0;
+        if visibility.is_visible != is_visible {
+            visibility.is_visible = is_visible;
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use bevy::prelude::*;
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
 
 use crate::{
-    animation::AnimationPlugin, assets::GameAssets, player::PlayerPlugin,
-    projectiles::ProjectilePlugin, GameState,
+    animation::AnimationPlugin, assets::GameAssets, cleanup::cleanup, enemy::EnemyPlugin, feedback::HitFeedbackPlugin,
+    gameover::GameOverPlugin, health::HealthPlugin, particles::ParticlePlugin, pause::PausePlugin, player::PlayerPlugin,
+    projectiles::ProjectilePlugin, shake::ScreenShakePlugin, ui::hud::HudPlugin, wave::WavePlugin, GameState,
 };
 
 pub const MAP_LEFT_BOUND: f32 = -210.0;
@@ -11,27 +12,79 @@ pub const MAP_RIGHT_BOUND: f32 = 210.0;
 pub const MAP_DOWN_BOUND: f32 = -160.0;
 pub struct GamePlugin;
 
+// Everything spawned for a run of the arena, despawned when leaving InGame.
+#[derive(Component, Default, Clone)]
+pub struct OnlyInGame;
+
+// Points scored during the current run.
+#[derive(Default)]
+pub struct Score(pub u32);
+
+/*
+* Sent every time points are scored, with the new total.
+*/
+pub struct ScoreEvent {
+    pub total: u32,
+}
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(PlayerPlugin)
+        app.init_resource::<Score>()
+            .add_event::<ScoreEvent>()
+            .add_plugin(PlayerPlugin)
             .add_plugin(ProjectilePlugin)
             .add_plugin(AnimationPlugin)
-            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_map));
+            .add_plugin(HealthPlugin)
+            .add_plugin(EnemyPlugin)
+            .add_plugin(WavePlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(GameOverPlugin)
+            .add_plugin(HudPlugin)
+            .add_plugin(ScreenShakePlugin)
+            .add_plugin(ParticlePlugin)
+            .add_plugin(HitFeedbackPlugin)
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame)
+                    .with_system(setup_map)
+                    .with_system(reset_score),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::InGame).with_system(cleanup::<OnlyInGame>),
+            );
     }
 }
 
 fn setup_map(mut commands: Commands, game_assets: Res<GameAssets>) {
     // Spawn edge tiles
-    commands.spawn_bundle(SpriteBundle {
-        texture: game_assets.arena_background.clone(),
-        transform: Transform {
-            translation: Vec3 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: game_assets.arena_background.clone(),
+            transform: Transform {
+                translation: Vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                ..Default::default()
             },
             ..Default::default()
-        },
-        ..Default::default()
-    });
+        })
+        .insert(OnlyInGame);
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    score.0 = 0;
+}
+
+/*
+* Chained after the FixedTimestep of every gameplay system set. `with_run_criteria` replaces
+* the criteria of `SystemSet::on_update`, so the state has to be checked here instead.
+* Stops the simulation in every other state, including while paused.
+*/
+pub fn run_if_in_game(In(should_run): In<ShouldRun>, state: Res<State<GameState>>) -> ShouldRun {
+    if state.current() == &GameState::InGame {
+        should_run
+    } else {
+        ShouldRun::No
+    }
 }
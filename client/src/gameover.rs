@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::MainMenuAssets,
+    cleanup::cleanup,
+    components::Player,
+    game::Score,
+    health::DeathEvent,
+    ui::{
+        utils::basic_text,
+        widgets::{spawn_button_list, WidgetEvent, WidgetSystem, DARKCOLOR, LIGHTCOLOR},
+    },
+    wave::WaveDirector,
+    GameState,
+};
+
+pub struct GameOverPlugin;
+
+// IN SECONDS, time to look at the dead player before the game over screen.
+pub const GAME_OVER_DELAY: f32 = 1.5;
+
+#[derive(Component, Default, Clone)]
+pub struct OnlyInGameOver;
+
+/*
+* Entries of the game over menu, top to bottom.
+*/
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Component)]
+pub enum GameOverMenuItem {
+    Restart,
+    MainMenu,
+}
+
+// Started when the player dies.
+#[derive(Default)]
+struct GameOverDelay(Option<Timer>);
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameOverDelay>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(reset_delay))
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(detect_game_over))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(spawn_game_over_menu),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(game_over_controls.after(WidgetSystem)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver).with_system(cleanup::<OnlyInGameOver>),
+            );
+    }
+}
+
+fn reset_delay(mut delay: ResMut<GameOverDelay>) {
+    delay.0 = None;
+}
+
+fn detect_game_over(
+    mut death_events: EventReader<DeathEvent>,
+    player_query: Query<Entity, With<Player>>,
+    mut delay: ResMut<GameOverDelay>,
+    mut game_state: ResMut<State<GameState>>,
+    time: Res<Time>,
+) {
+    for event in death_events.iter() {
+        if player_query.get(event.entity).is_ok() {
+            delay.0 = Some(Timer::from_seconds(GAME_OVER_DELAY, false));
+        }
+    }
+    if let Some(timer) = delay.0.as_mut() {
+        if timer.tick(time.delta()).just_finished() {
+            if let Err(error) = game_state.set(GameState::GameOver) {
+                warn!("Could not end the game: {}", error);
+            }
+        }
+    }
+}
+
+fn spawn_game_over_menu(
+    mut commands: Commands,
+    mm_assets: Res<MainMenuAssets>,
+    director: Res<WaveDirector>,
+    score: Res<Score>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0., 0., 0.).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInGameOver)
+        .with_children(|root| {
+            root.spawn_bundle(basic_text(
+                "Game Over",
+                32.,
+                mm_assets.alagrad.clone(),
+                None,
+                None,
+                None,
+                LIGHTCOLOR,
+            ));
+            root.spawn_bundle(basic_text(
+                &format!("Reached wave {} - Score {}", director.wave, score.0),
+                12.,
+                mm_assets.alagrad.clone(),
+                Some(10.),
+                Some(20.),
+                None,
+                DARKCOLOR,
+            ));
+            spawn_button_list(
+                root,
+                &[
+                    ("Restart", GameOverMenuItem::Restart),
+                    ("Main Menu", GameOverMenuItem::MainMenu),
+                ],
+                mm_assets.alagrad.clone(),
+                0,
+            );
+        });
+}
+
+fn game_over_controls(
+    mut game_state: ResMut<State<GameState>>,
+    mut widget_events: EventReader<WidgetEvent>,
+    item_query: Query<&GameOverMenuItem>,
+) {
+    let pressed = widget_events
+        .iter()
+        .filter_map(|event| match event {
+            WidgetEvent::Pressed(entity) => item_query.get(*entity).ok().copied(),
+            _ => None,
+        })
+        .next();
+    let next = match pressed {
+        Some(GameOverMenuItem::Restart) => GameState::InGame,
+        Some(GameOverMenuItem::MainMenu) => GameState::MainMenu,
+        None => return,
+    };
+    // Clicking twice on the same frame queues the change twice.
+    if let Err(error) = game_state.set(next) {
+        warn!("Could not leave the game over screen: {}", error);
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{ecs::system::IntoChainSystem, prelude::*, time::FixedTimestep};
+
+use crate::{
+    animation::animate_sprite,
+    components::{
+        CharacterState, Dead, ECharacterAttackState, ECharacterMovementState, Health, HitReaction,
+    },
+    game::{run_if_in_game, MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
+    projectiles::ProjectileHitEvent,
+    TIME_STEP,
+};
+
+pub struct HealthPlugin;
+
+// Knockback speed lost per second, as a fraction of the current speed.
+pub const KNOCKBACK_DECAY: f32 = 10.;
+
+/*
+* Sent every time a character loses health.
+*/
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub amount: f32,
+    pub point: Vec2,
+    pub critical: bool,
+    // This hit brought the target's health to zero.
+    pub lethal: bool,
+}
+
+/*
+* Sent once when a character's health reaches zero.
+*/
+pub struct DeathEvent {
+    pub entity: Entity,
+}
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DeathEvent>()
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
+                    .with_system(apply_projectile_damage.before(check_deaths))
+                    .with_system(update_hit_reactions.after(apply_projectile_damage))
+                    // Dead is inserted at the end of the stage, the dead clip starts next tick.
+                    .with_system(check_deaths.after(animate_sprite)),
+            );
+    }
+}
+
+/*
+* Invulnerable characters still stop the projectile but take no damage. The window starts
+* right away, so overlapping shots landing on the same tick only hit once.
+*/
+pub fn apply_projectile_damage(
+    mut hit_events: EventReader<ProjectileHitEvent>,
+    mut query: Query<(&mut Health, Option<&mut HitReaction>), Without<Dead>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for hit in hit_events.iter() {
+        if let Ok((mut health, hit_reaction)) = query.get_mut(hit.target) {
+            if let Some(mut hit_reaction) = hit_reaction {
+                if hit_reaction.is_invulnerable() {
+                    continue;
+                }
+                hit_reaction.invulnerability.reset();
+                hit_reaction.knockback = hit.direction * hit_reaction.knockback_speed;
+            }
+            health.current = (health.current - hit.damage).max(0.);
+            damage_events.send(DamageEvent {
+                target: hit.target,
+                source: hit.shooter,
+                amount: hit.damage,
+                point: hit.point,
+                critical: hit.critical,
+                lethal: health.current <= 0.,
+            });
+        }
+    }
+}
+
+/*
+* Ticks the invulnerability windows and pushes knocked back characters, clamped to the map
+* like regular movement.
+*/
+fn update_hit_reactions(mut query: Query<(&mut HitReaction, &mut Transform)>, time: Res<Time>) {
+    for (mut hit_reaction, mut transform) in &mut query {
+        hit_reaction.invulnerability.tick(time.delta());
+        if hit_reaction.knockback == Vec2::ZERO {
+            continue;
+        }
+        let mut new_position =
+            transform.translation + hit_reaction.knockback.extend(0.) * time.delta_seconds();
+        new_position.x = new_position.x.clamp(MAP_LEFT_BOUND, MAP_RIGHT_BOUND);
+        new_position.y = new_position.y.clamp(MAP_DOWN_BOUND, MAP_UP_BOUND);
+        transform.translation = new_position;
+        let decay = (1. - KNOCKBACK_DECAY * time.delta_seconds()).max(0.);
+        hit_reaction.knockback *= decay;
+        // Stop once it's no longer visible
+        if hit_reaction.knockback.length() < 1. {
+            hit_reaction.knockback = Vec2::ZERO;
+        }
+    }
+}
+
+fn check_deaths(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Health, &mut CharacterState), (Changed<Health>, Without<Dead>)>,
+    mut death_events: EventWriter<DeathEvent>,
+) {
+    for (entity, health, mut character_state) in &mut query {
+        if health.current <= 0. {
+            commands.entity(entity).insert(Dead);
+            // Dead characters stop acting, so clear whatever they were doing.
+            character_state.0 .0 = ECharacterMovementState::IDLE;
+            character_state.0 .1 = ECharacterAttackState::IDLE;
+            death_events.send(DeathEvent { entity });
+        }
+    }
+}
@@ -0,0 +1,253 @@
+/**
+ * Loads every asset collection while showing a progress bar, then continues to the main menu.
+ * A missing or broken file stops the loading screen with the path that failed.
+ */
+use bevy::{
+    asset::{HandleId, LoadState},
+    prelude::*,
+};
+use bevy_asset_loader::prelude::AssetCollection;
+
+use crate::{
+    assets::{EnemyAssets, GameAssets, MainMenuAssets},
+    cleanup::cleanup,
+    ui::{
+        utils::basic_text,
+        widgets::{DARKCOLOR, LIGHTCOLOR},
+    },
+    GameState,
+};
+
+pub struct LoadingPlugin;
+
+// Loaded on its own so the loading screen has text as early as possible.
+pub const LOADING_FONT: &str = "mainmenu/alagrad.ttf";
+pub const LOADING_BAR_SIZE: Vec2 = Vec2::new(240., 10.);
+
+#[derive(Component, Default, Clone)]
+pub struct OnlyInLoading;
+
+/*
+* Structs to get the different text components
+*/
+#[derive(Component)]
+struct LoadingBarFill;
+
+#[derive(Component)]
+struct LoadingPathText;
+
+/*
+* Every handle of every collection, in the order they were requested.
+*/
+#[derive(Default)]
+struct LoadingHandles(Vec<HandleUntyped>);
+
+#[derive(Default)]
+struct LoadingStatus {
+    loaded: usize,
+    total: usize,
+    // Path of the first asset that isn't loaded yet.
+    current: Option<String>,
+    failed: Option<String>,
+}
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingHandles>()
+            .init_resource::<LoadingStatus>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::AssetLoading)
+                    .with_system(start_loading.exclusive_system())
+                    .with_system(spawn_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::AssetLoading)
+                    .with_system(check_loading)
+                    .with_system(update_loading_screen.after(check_loading))
+                    // Runs at the start of the next frame, once everything is loaded
+                    .with_system(finish_loading.exclusive_system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::AssetLoading).with_system(cleanup::<OnlyInLoading>),
+            );
+    }
+}
+
+fn start_loading(world: &mut World) {
+    let mut handles = MainMenuAssets::load(world);
+    handles.extend(GameAssets::load(world));
+    handles.extend(EnemyAssets::load(world));
+    world.insert_resource(LoadingHandles(handles));
+    world.insert_resource(LoadingStatus::default());
+}
+
+fn spawn_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load(LOADING_FONT);
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0., 0., 0.).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInLoading)
+        .with_children(|root| {
+            root.spawn_bundle(basic_text(
+                "Loading",
+                24.,
+                font.clone(),
+                None,
+                Some(20.),
+                None,
+                LIGHTCOLOR,
+            ));
+            root.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(LOADING_BAR_SIZE.x), Val::Px(LOADING_BAR_SIZE.y)),
+                    ..Default::default()
+                },
+                color: DARKCOLOR.into(),
+                ..Default::default()
+            })
+            .with_children(|bar| {
+                bar.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                        ..Default::default()
+                    },
+                    color: LIGHTCOLOR.into(),
+                    ..Default::default()
+                })
+                .insert(LoadingBarFill);
+            });
+            root.spawn_bundle(basic_text("", 10., font, Some(10.), None, None, DARKCOLOR))
+                .insert(LoadingPathText);
+        });
+}
+
+fn check_loading(
+    handles: Res<LoadingHandles>,
+    mut status: ResMut<LoadingStatus>,
+    asset_server: Res<AssetServer>,
+) {
+    if status.failed.is_some() {
+        return;
+    }
+    let path = |handle: &HandleUntyped| match asset_server.get_handle_path(handle) {
+        Some(path) => path.path().display().to_string(),
+        None => "an unknown file".to_string(),
+    };
+    let mut loaded = 0;
+    let mut current = None;
+    for handle in &handles.0 {
+        match asset_server.get_load_state(handle) {
+            LoadState::Loaded => loaded += 1,
+            LoadState::Failed => {
+                let failed = path(handle);
+                error!("Could not load {}", failed);
+                status.failed = Some(failed);
+                return;
+            }
+            _ => {
+                if current.is_none() {
+                    current = Some(path(handle));
+                }
+            }
+        }
+    }
+    status.loaded = loaded;
+    status.total = handles.0.len();
+    status.current = current;
+}
+
+fn update_loading_screen(
+    status: Res<LoadingStatus>,
+    mut bar_query: Query<(&mut Style, &mut UiColor), With<LoadingBarFill>>,
+    mut text_query: Query<&mut Text, With<LoadingPathText>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let progress = if status.total > 0 {
+        status.loaded as f32 / status.total as f32
+    } else {
+        0.
+    };
+    for (mut style, mut color) in &mut bar_query {
+        style.size.width = Val::Percent(progress * 100.);
+        if status.failed.is_some() {
+            *color = DARKCOLOR.into();
+        }
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = match (&status.failed, &status.current) {
+            (Some(failed), _) => format!(
+                "Could not load {}\nCheck that the file exists in the assets folder.",
+                failed
+            ),
+            (None, Some(current)) => {
+                format!("{}/{} - {}", status.loaded, status.total, current)
+            }
+            (None, None) => format!("{}/{}", status.loaded, status.total),
+        };
+        if status.failed.is_some() {
+            text.sections[0].style.color = LIGHTCOLOR;
+        }
+    }
+}
+
+fn finish_loading(world: &mut World) {
+    let status = world.resource::<LoadingStatus>();
+    if status.failed.is_some() || status.total == 0 || status.loaded < status.total {
+        return;
+    }
+    let main_menu_assets = MainMenuAssets::create(world);
+    world.insert_resource(main_menu_assets);
+    let game_assets = GameAssets::create(world);
+    world.insert_resource(game_assets);
+    let enemy_assets = EnemyAssets::create(world);
+    world.insert_resource(enemy_assets);
+    fit_atlases_to_images(world);
+    world.insert_resource(LoadingHandles::default());
+    world.insert_resource(LoadingStatus::default());
+    world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::MainMenu)
+        .unwrap();
+}
+
+/*
+* assets.rs sets the tile size and the four direction layout of each sheet.
+* Sheets of another size, like ones with diagonal frames, get a grid that covers all of their tiles.
+*/
+fn fit_atlases_to_images(world: &mut World) {
+    world.resource_scope(|world, mut atlases: Mut<Assets<TextureAtlas>>| {
+        let images = world.resource::<Assets<Image>>();
+        let ids: Vec<HandleId> = atlases.ids().collect();
+        for id in ids {
+            let atlas = match atlases.get_mut(id) {
+                Some(atlas) => atlas,
+                None => continue,
+            };
+            let image_size = match images.get(&atlas.texture) {
+                Some(image) => image.size(),
+                None => continue,
+            };
+            let tile_size = match atlas.textures.first() {
+                Some(tile) => tile.size(),
+                None => continue,
+            };
+            if atlas.size == image_size || tile_size.min_element() <= 0. {
+                continue;
+            }
+            let columns = (image_size.x / tile_size.x) as usize;
+            let rows = (image_size.y / tile_size.y) as usize;
+            *atlas = TextureAtlas::from_grid(atlas.texture.clone(), tile_size, columns, rows);
+        }
+    });
+}
@@ -1,7 +1,5 @@
-use assets::{MainMenuAssets, GameAssets};
 use bevy::{prelude::*, render::texture::ImageSettings};
 use bevy_inspector_egui::WorldInspectorPlugin;
-use bevy_asset_loader::prelude::*;
 const TIME_STEP: f32 = 1.0 / 60.0;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -9,6 +7,9 @@ pub enum GameState {
     AssetLoading,
     MainMenu,
     InGame,
+    // Pushed on top of InGame, the world stays rendered but nothing updates.
+    Paused,
+    GameOver,
 }
 
 impl Default for GameState {
@@ -17,34 +18,51 @@ impl Default for GameState {
     }
 }
 
+mod actions;
+mod aseprite;
 mod components;
+mod cleanup;
+mod config;
+mod credits;
 mod game;
+mod gameover;
 mod mainmenu;
+mod pause;
 mod player;
 mod ui;
 mod projectiles;
+mod settings;
+mod shake;
 mod assets;
 mod animation;
+mod enemy;
+mod feedback;
+mod health;
+mod loading;
+mod particles;
+mod placeholder;
+mod wave;
+mod weapons;
 fn main() {
+    let settings = settings::Settings::load();
     App::new()
         .insert_resource(ImageSettings::default_nearest())
-        .insert_resource(WindowDescriptor {
-            title: "2d_shooter".to_string(),
-            width: 480.,
-            height: 384.,
-            resizable: false,
-            ..default()
+        .insert_resource(settings.window_descriptor())
+        .insert_resource(settings)
+        // Serves generated art and sounds for files missing from the assets folder
+        .add_plugins_with(DefaultPlugins, |group| {
+            group.add_before::<bevy::asset::AssetPlugin, _>(placeholder::PlaceholderAssetsPlugin)
         })
-        .add_loading_state(
-            LoadingState::new(GameState::AssetLoading)
-                .continue_to_state(GameState::MainMenu)
-                .with_collection::<MainMenuAssets>()
-                .with_collection::<GameAssets>()
-        )
-        .add_plugins(DefaultPlugins)
+        .add_plugin(weapons::WeaponPlugin)
+        .add_plugin(aseprite::AsepritePlugin)
+        .add_plugin(credits::CreditsPlugin)
+        .add_plugin(loading::LoadingPlugin)
+        .add_plugin(actions::ActionsPlugin)
+        .add_plugin(ui::widgets::WidgetPlugin)
+        .add_plugin(settings::SettingsPlugin)
+        .add_plugin(ui::cursor::CursorPlugin)
         .add_startup_system(setup)
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
-        .add_system(bevy::window::close_on_esc)
         .add_plugin(mainmenu::MainMenuPlugin)
         .add_plugin(game::GamePlugin)
         .add_state(GameState::AssetLoading)
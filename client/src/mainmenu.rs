@@ -1,8 +1,18 @@
 use bevy::{app::AppExit, prelude::*};
 
 use crate::{
+    actions::{Action, ActionState},
     assets::MainMenuAssets,
-    ui::utils::basic_text,
+    cleanup::cleanup,
+    credits::{spawn_credits_list, Credits},
+    settings::{spawn_settings_menu, Settings, SettingsClosed, SettingsSystem},
+    ui::{
+        utils::basic_text,
+        widgets::{
+            spawn_button, spawn_button_list, spawn_confirm_dialog, DialogClosed, WidgetEvent,
+            WidgetSystem, LIGHTCOLOR,
+        },
+    },
     GameState,
 };
 pub struct MainMenuPlugin;
@@ -13,13 +23,7 @@ pub struct OnlyInMainMenu;
 #[derive(Component, Default, Clone)]
 pub struct OnlyInCredits;
 
-const DARKCOLOR: Color = Color::rgb(115. / 255., 23. / 255., 45. / 255.);
-const LIGHTCOLOR: Color = Color::rgb(180. / 255., 32. / 255., 42. / 255.);
-/*
-* Structs to get the different text components
-*/
-#[derive(Component)]
-struct AnimateTextFields();
+const QUIT_DIALOG: &str = "quit";
 
 /*
 * Used to decide whether or not to display credits
@@ -28,6 +32,7 @@ struct AnimateTextFields();
 pub enum MainMenuState {
     SplashScreen,
     Credits,
+    Settings,
 }
 
 impl Default for MainMenuState {
@@ -37,34 +42,37 @@ impl Default for MainMenuState {
 }
 
 /*
-* Used to decide whether or not to display credits
+* Buttons of the main menu and the credits screen
 */
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub enum HoverState {
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Component)]
+pub enum MainMenuButton {
     PlayGame,
+    Settings,
     Credits,
     Quit,
+    Back,
 }
 
-impl Default for HoverState {
-    fn default() -> HoverState {
-        HoverState::PlayGame
-    }
-}
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(HoverState::default())
-            .insert_resource(MainMenuState::default())
-            .add_state(HoverState::default())
+        app.insert_resource(MainMenuState::default())
             .add_state(MainMenuState::default())
             .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_ui))
-            .add_system_set(SystemSet::on_update(GameState::MainMenu).with_system(ui_controls))
-            .add_system_set(SystemSet::on_update(GameState::MainMenu).with_system(animate_text))
-            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(cleanup));
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(ui_controls.after(WidgetSystem).after(SettingsSystem)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::MainMenu).with_system(cleanup::<OnlyInMainMenu>),
+            );
     }
 }
 
 fn spawn_ui(mut commands: Commands, mm_assets: Res<MainMenuAssets>) {
+    spawn_splash_screen(&mut commands, &mm_assets);
+}
+
+fn spawn_splash_screen(commands: &mut Commands, mm_assets: &MainMenuAssets) {
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -79,8 +87,6 @@ fn spawn_ui(mut commands: Commands, mm_assets: Res<MainMenuAssets>) {
         })
         .insert(OnlyInMainMenu)
         .with_children(|root| {
-            let font = mm_assets.alagrad.clone();
-
             root.spawn_bundle(NodeBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
@@ -102,235 +108,147 @@ fn spawn_ui(mut commands: Commands, mm_assets: Res<MainMenuAssets>) {
                     ..Default::default()
                 });
             });
-            root.spawn_bundle(basic_text(
-                "Play game",
-                24.,
-                font.clone(),
-                Some(70.),
-                None,
-                None,
-                LIGHTCOLOR,
-            ))
-            .insert(AnimateTextFields());
+            // Pushes the buttons under the title of the splash screen
+            root.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Auto, Val::Px(50.)),
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            });
+            spawn_button_list(
+                root,
+                &[
+                    ("Play game", MainMenuButton::PlayGame),
+                    ("Settings", MainMenuButton::Settings),
+                    ("Credits", MainMenuButton::Credits),
+                    ("Quit", MainMenuButton::Quit),
+                ],
+                mm_assets.alagrad.clone(),
+                0,
+            );
+        });
+}
+
+fn spawn_credits(commands: &mut Commands, mm_assets: &MainMenuAssets, credits: &Assets<Credits>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0., 0., 0.).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInCredits)
+        .with_children(|root| {
             root.spawn_bundle(basic_text(
                 "Credits",
-                24.,
-                font.clone(),
-                Some(20.),
-                None,
-                None,
-                DARKCOLOR,
-            ))
-            .insert(AnimateTextFields());
-            root.spawn_bundle(basic_text(
-                "Quit",
-                24.,
-                font.clone(),
-                Some(20.),
+                32.,
+                mm_assets.alagrad.clone(),
                 None,
+                Some(10.),
                 None,
-                DARKCOLOR,
-            ))
-            .insert(AnimateTextFields());
+                LIGHTCOLOR,
+            ));
+            spawn_credits_list(
+                root,
+                credits.get(&mm_assets.credits),
+                mm_assets.alagrad.clone(),
+            );
+            spawn_button(
+                root,
+                "Back",
+                mm_assets.alagrad.clone(),
+                0,
+                MainMenuButton::Back,
+            );
         });
 }
 
 fn ui_controls(
-    keyboard_input: Res<Input<KeyCode>>,
+    mut actions: ResMut<ActionState>,
     mut game_state: ResMut<State<GameState>>,
     mut main_menu_state: ResMut<State<MainMenuState>>,
-    mut hover_state: ResMut<State<HoverState>>,
-    mut credits_query: Query<Entity, With<OnlyInCredits>>,
-    mut main_menu_query: Query<Entity, With<OnlyInMainMenu>>,
+    mut widget_events: EventReader<WidgetEvent>,
+    mut dialog_events: EventReader<DialogClosed>,
+    mut settings_closed_events: EventReader<SettingsClosed>,
+    settings: Res<Settings>,
+    credits: Res<Assets<Credits>>,
+    button_query: Query<&MainMenuButton>,
+    credits_query: Query<Entity, With<OnlyInCredits>>,
+    main_menu_query: Query<Entity, With<OnlyInMainMenu>>,
     mut app_exit_events: EventWriter<AppExit>,
-    audio: Res<Audio>,
     mut commands: Commands,
     mm_assets: Res<MainMenuAssets>,
 ) {
-    let credit_entity = credits_query.get_single_mut();
-    if main_menu_state.current() == &MainMenuState::SplashScreen {
-        if keyboard_input.just_released(KeyCode::S) || keyboard_input.just_released(KeyCode::Down) {
-            match hover_state.current() {
-                HoverState::PlayGame => {
-                    hover_state.set(HoverState::Credits).unwrap();
-                    audio.play(mm_assets.ui_button.clone());
-                }
-                HoverState::Credits => {
-                    hover_state.set(HoverState::Quit).unwrap();
-                    audio.play(mm_assets.ui_button.clone());
-                }
-                HoverState::Quit => {
-                    hover_state.set(HoverState::PlayGame).unwrap();
-                    audio.play(mm_assets.ui_button.clone());
-                }
-            }
+    let mut pressed: Vec<MainMenuButton> = widget_events
+        .iter()
+        .filter_map(|event| match event {
+            WidgetEvent::Pressed(entity) => button_query.get(*entity).ok().copied(),
+            _ => None,
+        })
+        .collect();
+    for event in dialog_events.iter() {
+        if event.id == QUIT_DIALOG && event.confirmed {
+            app_exit_events.send(AppExit);
         }
-        if keyboard_input.just_released(KeyCode::W) || keyboard_input.just_released(KeyCode::Up) {
-            match hover_state.current() {
-                HoverState::PlayGame => {
-                    hover_state.set(HoverState::Quit).unwrap();
-                    audio.play(mm_assets.ui_button.clone());
-                }
-                HoverState::Credits => {
-                    hover_state.set(HoverState::PlayGame).unwrap();
-                    audio.play(mm_assets.ui_button.clone());
-                }
-                HoverState::Quit => {
-                    hover_state.set(HoverState::Credits).unwrap();
-                    audio.play(mm_assets.ui_button.clone());
+    }
+    if actions.just_released(Action::MenuBack) {
+        actions.consume(Action::MenuBack);
+        // Backing out of the splash screen asks to quit
+        pressed.push(match main_menu_state.current() {
+            MainMenuState::SplashScreen => MainMenuButton::Quit,
+            _ => MainMenuButton::Back,
+        });
+    }
+    // The settings menu despawns itself
+    if settings_closed_events.iter().next().is_some() {
+        main_menu_state.set(MainMenuState::SplashScreen).unwrap();
+        spawn_splash_screen(&mut commands, &mm_assets);
+    }
+
+    // Only the first press counts, the screen it was on is gone after it.
+    if let Some(button) = pressed.first() {
+        match button {
+            MainMenuButton::PlayGame => game_state.set(GameState::InGame).unwrap(),
+            MainMenuButton::Settings => {
+                main_menu_state.set(MainMenuState::Settings).unwrap();
+                for entity in &main_menu_query {
+                    commands.entity(entity).despawn_recursive();
                 }
+                let menu = spawn_settings_menu(&mut commands, &settings, mm_assets.alagrad.clone());
+                commands.entity(menu).insert(OnlyInMainMenu);
             }
-        }
-        if keyboard_input.just_released(KeyCode::Return) {
-            match hover_state.current() {
-                HoverState::PlayGame => game_state.set(GameState::InGame).unwrap(),
-                HoverState::Credits => {
-                    main_menu_state.set(MainMenuState::Credits).unwrap();
-                    /*
-                    Spawn the credits information
-                    */
-                    commands
-                        .entity(main_menu_query.single_mut())
-                        .despawn_recursive();
-                    commands
-                        .spawn_bundle(NodeBundle {
-                            style: Style {
-                                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
-                                flex_direction: FlexDirection::ColumnReverse,
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                ..Default::default()
-                            },
-                            color: Color::rgb(0., 0., 0.).into(),
-                            ..Default::default()
-                        })
-                        .insert(OnlyInCredits)
-                        .with_children(|root| {
-                            root.spawn_bundle(basic_text(
-                                "In CREDITS.txt. Return to exit.",
-                                10.,
-                                mm_assets.alagrad.clone(),
-                                None,
-                                None,
-                                None,
-                                Color::rgb(115. / 255., 23. / 255., 45. / 255.),
-                            ));
-                        });
+            MainMenuButton::Credits => {
+                main_menu_state.set(MainMenuState::Credits).unwrap();
+                for entity in &main_menu_query {
+                    commands.entity(entity).despawn_recursive();
                 }
-                HoverState::Quit => app_exit_events.send(AppExit),
+                spawn_credits(&mut commands, &mm_assets, &credits);
             }
-        }
-    } else {
-        if keyboard_input.just_released(KeyCode::Return) {
-            // Despawn all of the credits information
-            commands.entity(credit_entity.unwrap()).despawn_recursive();
-            //Set main menu state
-            main_menu_state.set(MainMenuState::SplashScreen).unwrap();
-            //Default the hover state
-            hover_state.set(HoverState::default()).unwrap();
-            /*
-             * TODO: Put this into a seperate method so we don't have it in two places.
-             */
-
-            commands
-                .spawn_bundle(NodeBundle {
-                    style: Style {
-                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
-                        flex_direction: FlexDirection::ColumnReverse,
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..Default::default()
-                    },
-                    color: Color::rgb(0., 0., 0.).into(),
-                    ..Default::default()
-                })
-                .insert(OnlyInMainMenu)
-                .with_children(|root| {
-                    root.spawn_bundle(NodeBundle {
-                        style: Style {
-                            position_type: PositionType::Absolute,
-                            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::FlexEnd,
-                            ..Default::default()
-                        },
-                        color: Color::NONE.into(),
-                        ..Default::default()
-                    })
-                    .with_children(|parent| {
-                        parent.spawn_bundle(ImageBundle {
-                            image: UiImage(mm_assets.main_menu_screen.clone()),
-                            style: Style {
-                                size: Size::new(Val::Px(480.), Val::Px(384.)),
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        });
-                    });
-                    root.spawn_bundle(basic_text(
-                        "Play game",
-                        24.,
-                        mm_assets.alagrad.clone(),
-                        Some(70.),
-                        None,
-                        None,
-                        LIGHTCOLOR,
-                    ))
-                    .insert(AnimateTextFields());
-                    root.spawn_bundle(basic_text(
-                        "Credits",
-                        24.,
+            MainMenuButton::Quit => {
+                if let Ok(root) = main_menu_query.get_single() {
+                    spawn_confirm_dialog(
+                        &mut commands,
+                        root,
+                        QUIT_DIALOG,
+                        "Quit the game?",
                         mm_assets.alagrad.clone(),
-                        Some(20.),
-                        None,
-                        None,
-                        DARKCOLOR,
-                    ))
-                    .insert(AnimateTextFields());
-                    root.spawn_bundle(basic_text(
-                        "Quit",
-                        24.,
-                        mm_assets.alagrad.clone(),
-                        Some(20.),
-                        None,
-                        None,
-                        DARKCOLOR,
-                    ))
-                    .insert(AnimateTextFields());
-                });
-        }
-    }
-}
-
-fn animate_text(
-    mut text_query: Query<&mut Text, With<AnimateTextFields>>,
-    hover_state: Res<State<HoverState>>,
-) {
-    for mut text in text_query.iter_mut() {
-        if text.sections[0].value == String::from("Play game") {
-            if hover_state.current() == &HoverState::PlayGame {
-                text.sections[0].style.color = LIGHTCOLOR;
-            } else {
-                text.sections[0].style.color = DARKCOLOR;
-            }
-        } else if text.sections[0].value == String::from("Credits") {
-            if hover_state.current() == &HoverState::Credits {
-                text.sections[0].style.color = LIGHTCOLOR;
-            } else {
-                text.sections[0].style.color = DARKCOLOR;
+                    );
+                }
             }
-        } else {
-            if hover_state.current() == &HoverState::Quit {
-                text.sections[0].style.color = LIGHTCOLOR;
-            } else {
-                text.sections[0].style.color = DARKCOLOR;
+            MainMenuButton::Back => {
+                main_menu_state.set(MainMenuState::SplashScreen).unwrap();
+                for entity in &credits_query {
+                    commands.entity(entity).despawn_recursive();
+                }
+                spawn_splash_screen(&mut commands, &mm_assets);
             }
         }
     }
 }
-
-fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInMainMenu>>) {
-    for entity in query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
@@ -0,0 +1,243 @@
+/**
+ * CPU particles drawn as plain colored sprites. A fixed pool of sprites is spawned once and
+ * reused, effects that need more particles than are free only get what's left.
+ */
+use bevy::{ecs::system::IntoChainSystem, prelude::*, time::FixedTimestep};
+use rand::Rng;
+
+use crate::{game::run_if_in_game, GameState, TIME_STEP};
+
+pub struct ParticlePlugin;
+
+pub const MAX_PARTICLES: usize = 512;
+// Above the arena, under characters and arrows.
+pub const PARTICLE_Z: f32 = 0.5;
+
+/*
+* What an emission looks like. Ranges are picked from at random for each particle,
+* curves are keyframes over the lifetime of a particle, from 0 to 1.
+*/
+pub struct ParticleEffect {
+    pub count: (u32, u32),
+    // IN SECONDS
+    pub lifetime: (f32, f32),
+    // IN PIXELS PER SECOND
+    pub speed: (f32, f32),
+    // Total cone in degrees around the emit direction, 360 for every direction.
+    pub spread: f32,
+    // Fraction of the velocity lost per second.
+    pub drag: f32,
+    pub color: &'static [(f32, Color)],
+    // Side of the square, in pixels.
+    pub size: &'static [(f32, f32)],
+}
+
+pub const ARROW_TRAIL: ParticleEffect = ParticleEffect {
+    count: (1, 1),
+    lifetime: (0.15, 0.25),
+    speed: (0., 10.),
+    spread: 360.,
+    drag: 4.,
+    color: &[
+        (0., Color::rgba(0.9, 0.9, 0.8, 0.6)),
+        (1., Color::rgba(0.9, 0.9, 0.8, 0.)),
+    ],
+    size: &[(0., 2.), (1., 1.)],
+};
+
+pub const ARROW_IMPACT: ParticleEffect = ParticleEffect {
+    count: (6, 10),
+    lifetime: (0.2, 0.4),
+    speed: (40., 120.),
+    spread: 120.,
+    drag: 6.,
+    color: &[
+        (0., Color::rgba(1., 0.95, 0.7, 1.)),
+        (0.4, Color::rgba(0.8, 0.6, 0.4, 0.8)),
+        (1., Color::rgba(0.5, 0.4, 0.3, 0.)),
+    ],
+    size: &[(0., 3.), (1., 1.)],
+};
+
+pub const FOOTSTEP_DUST: ParticleEffect = ParticleEffect {
+    count: (2, 4),
+    lifetime: (0.3, 0.5),
+    speed: (5., 20.),
+    spread: 180.,
+    drag: 3.,
+    color: &[
+        (0., Color::rgba(0.6, 0.55, 0.45, 0.5)),
+        (1., Color::rgba(0.6, 0.55, 0.45, 0.)),
+    ],
+    size: &[(0., 2.), (0.5, 4.), (1., 5.)],
+};
+
+/*
+* Sent to emit an effect at a position, `direction` is the center of its cone.
+*/
+pub struct ParticleEvent {
+    pub effect: &'static ParticleEffect,
+    pub position: Vec2,
+    pub direction: Vec2,
+}
+
+#[derive(Component, Default)]
+struct Particle {
+    active: bool,
+    age: f32,
+    lifetime: f32,
+    velocity: Vec2,
+    drag: f32,
+    color: &'static [(f32, Color)],
+    size: &'static [(f32, f32)],
+}
+
+// Pool entities that aren't showing a particle.
+#[derive(Default)]
+struct ParticlePool {
+    free: Vec<Entity>,
+}
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ParticleEvent>()
+            .init_resource::<ParticlePool>()
+            .add_startup_system(spawn_particle_pool)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
+                    .with_system(update_particles)
+                    .with_system(emit_particles.after(update_particles)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(clear_particles));
+    }
+}
+
+fn spawn_particle_pool(mut commands: Commands, mut pool: ResMut<ParticlePool>) {
+    for _ in 0..MAX_PARTICLES {
+        let entity = commands
+            .spawn_bundle(SpriteBundle {
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .insert(Particle::default())
+            .id();
+        pool.free.push(entity);
+    }
+}
+
+fn emit_particles(
+    mut particle_events: EventReader<ParticleEvent>,
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    let mut rng = rand::thread_rng();
+    for event in particle_events.iter() {
+        let effect = event.effect;
+        let count = rng.gen_range(effect.count.0..=effect.count.1);
+        let direction = event.direction.try_normalize().unwrap_or(Vec2::X);
+        for _ in 0..count {
+            // Capped: heavy fights drop particles instead of spawning more sprites
+            let entity = match pool.free.pop() {
+                Some(entity) => entity,
+                None => break,
+            };
+            let (mut particle, mut transform, mut sprite, mut visibility) =
+                match query.get_mut(entity) {
+                    Ok(particle) => particle,
+                    Err(_) => continue,
+                };
+            let angle = effect.spread.to_radians() * (rng.gen::<f32>() - 0.5);
+            let speed = rng.gen_range(effect.speed.0..=effect.speed.1);
+            *particle = Particle {
+                active: true,
+                age: 0.,
+                lifetime: rng.gen_range(effect.lifetime.0..=effect.lifetime.1),
+                velocity: Mat2::from_angle(angle) * direction * speed,
+                drag: effect.drag,
+                color: effect.color,
+                size: effect.size,
+            };
+            transform.translation = event.position.extend(PARTICLE_Z);
+            sprite.color = sample(effect.color, 0., lerp_color);
+            sprite.custom_size = Some(Vec2::splat(sample(effect.size, 0., lerp_f32)));
+            visibility.is_visible = true;
+        }
+    }
+}
+
+fn update_particles(
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite, mut visibility) in &mut query {
+        if !particle.active {
+            continue;
+        }
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            particle.active = false;
+            visibility.is_visible = false;
+            pool.free.push(entity);
+            continue;
+        }
+        let drag = (1. - particle.drag * delta).max(0.);
+        particle.velocity *= drag;
+        transform.translation += particle.velocity.extend(0.) * delta;
+        let t = particle.age / particle.lifetime;
+        sprite.color = sample(particle.color, t, lerp_color);
+        sprite.custom_size = Some(Vec2::splat(sample(particle.size, t, lerp_f32)));
+    }
+}
+
+fn clear_particles(
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(Entity, &mut Particle, &mut Visibility)>,
+) {
+    for (entity, mut particle, mut visibility) in &mut query {
+        if particle.active {
+            particle.active = false;
+            visibility.is_visible = false;
+            pool.free.push(entity);
+        }
+    }
+}
+
+/*
+* Curves
+*/
+fn sample<T: Copy>(keys: &[(f32, T)], t: f32, lerp: fn(T, T, f32) -> T) -> T {
+    let next = keys.iter().position(|(time, _)| *time > t);
+    match next {
+        Some(0) => keys[0].1,
+        Some(index) => {
+            let (start_time, start) = keys[index - 1];
+            let (end_time, end) = keys[index];
+            lerp(start, end, (t - start_time) / (end_time - start_time))
+        }
+        None => keys[keys.len() - 1].1,
+    }
+}
+
+fn lerp_f32(start: f32, end: f32, t: f32) -> f32 {
+    start + (end - start) * t
+}
+
+fn lerp_color(start: Color, end: Color, t: f32) -> Color {
+    let [r0, g0, b0, a0] = start.as_rgba_f32();
+    let [r1, g1, b1, a1] = end.as_rgba_f32();
+    Color::rgba(
+        lerp_f32(r0, r1, t),
+        lerp_f32(g0, g1, t),
+        lerp_f32(b0, b1, t),
+        lerp_f32(a0, a1, t),
+    )
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    assets::MainMenuAssets,
+    cleanup::cleanup,
+    settings::{spawn_settings_menu, Settings, SettingsClosed, SettingsMenu, SettingsSystem},
+    ui::{
+        utils::basic_text,
+        widgets::{spawn_button_list, WidgetEvent, WidgetSystem, LIGHTCOLOR},
+    },
+    GameState,
+};
+
+pub struct PausePlugin;
+
+#[derive(Component, Default, Clone)]
+pub struct OnlyInPause;
+
+/*
+* Entries of the pause menu, top to bottom.
+*/
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Component)]
+pub enum PauseMenuItem {
+    Resume,
+    Settings,
+    QuitToMainMenu,
+}
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_pause)
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused).with_system(
+                    pause_menu_controls
+                        .after(WidgetSystem)
+                        .after(SettingsSystem),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(cleanup::<OnlyInPause>),
+            );
+    }
+}
+
+fn toggle_pause(mut actions: ResMut<ActionState>, mut game_state: ResMut<State<GameState>>) {
+    if !actions.just_pressed(Action::Pause) {
+        return;
+    }
+    match game_state.current() {
+        GameState::InGame => {
+            actions.consume(Action::Pause);
+            if let Err(error) = game_state.push(GameState::Paused) {
+                warn!("Could not pause: {}", error);
+            }
+        }
+        GameState::Paused => {
+            actions.consume(Action::Pause);
+            resume(&mut game_state);
+        }
+        _ => {}
+    }
+}
+
+fn spawn_pause_menu(mut commands: Commands, mm_assets: Res<MainMenuAssets>) {
+    spawn_pause_overlay(&mut commands, &mm_assets);
+}
+
+fn spawn_pause_overlay(commands: &mut Commands, mm_assets: &MainMenuAssets) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            // See-through so the frozen arena stays visible
+            color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInPause)
+        .with_children(|root| {
+            root.spawn_bundle(basic_text(
+                "Paused",
+                32.,
+                mm_assets.alagrad.clone(),
+                None,
+                Some(20.),
+                None,
+                LIGHTCOLOR,
+            ));
+            spawn_button_list(
+                root,
+                &[
+                    ("Resume", PauseMenuItem::Resume),
+                    ("Settings", PauseMenuItem::Settings),
+                    ("Quit to Main Menu", PauseMenuItem::QuitToMainMenu),
+                ],
+                mm_assets.alagrad.clone(),
+                0,
+            );
+        });
+}
+
+fn pause_menu_controls(
+    mut actions: ResMut<ActionState>,
+    mut game_state: ResMut<State<GameState>>,
+    mut widget_events: EventReader<WidgetEvent>,
+    mut settings_closed_events: EventReader<SettingsClosed>,
+    item_query: Query<&PauseMenuItem>,
+    overlay_query: Query<Entity, (With<OnlyInPause>, Without<SettingsMenu>)>,
+    settings: Res<Settings>,
+    mm_assets: Res<MainMenuAssets>,
+    mut commands: Commands,
+) {
+    let pressed = widget_events
+        .iter()
+        .filter_map(|event| match event {
+            WidgetEvent::Pressed(entity) => item_query.get(*entity).ok().copied(),
+            _ => None,
+        })
+        .next();
+    // The settings menu despawns itself
+    if settings_closed_events.iter().next().is_some() {
+        spawn_pause_overlay(&mut commands, &mm_assets);
+    }
+    if actions.just_released(Action::MenuBack) {
+        actions.consume(Action::MenuBack);
+        resume(&mut game_state);
+    } else if let Some(item) = pressed {
+        match item {
+            PauseMenuItem::Resume => resume(&mut game_state),
+            PauseMenuItem::Settings => {
+                for entity in &overlay_query {
+                    commands.entity(entity).despawn_recursive();
+                }
+                let menu = spawn_settings_menu(&mut commands, &settings, mm_assets.alagrad.clone());
+                commands.entity(menu).insert(OnlyInPause);
+            }
+            // Clears the whole stack, so InGame is exited as well.
+            PauseMenuItem::QuitToMainMenu => {
+                if let Err(error) = game_state.replace(GameState::MainMenu) {
+                    warn!("Could not quit to the main menu: {}", error);
+                }
+            }
+        }
+    }
+}
+
+// A resume can already be queued when Pause and MenuBack land on the same frame.
+fn resume(game_state: &mut State<GameState>) {
+    if let Err(error) = game_state.pop() {
+        warn!("Could not resume: {}", error);
+    }
+}
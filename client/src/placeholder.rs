@@ -0,0 +1,588 @@
+/**
+ * Generated stand-ins for the art and sounds that aren't part of the repository.
+ * Wraps the default asset io, so a missing file is answered with a generated png, wav or
+ * aseprite file and the rest of the game loads it like any other file.
+ * The `placeholder_assets` feature uses the placeholders even when the real files exist.
+ */
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetIo, AssetIoError, FileType, Metadata},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use image::{imageops, ImageOutputFormat, Rgba, RgbaImage};
+
+use crate::game::{MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND};
+
+pub struct PlaceholderAssetsPlugin;
+
+pub const FORCE_PLACEHOLDERS: bool = cfg!(feature = "placeholder_assets");
+// Size of a spritesheet tile and the number of tiles, see the README for the layout.
+pub const TILE_SIZE: u32 = 10;
+pub const TILE_COUNT: u32 = 49;
+// Same frame durations as the bundled `.anim.ron` files, IN MILLISECONDS
+pub const ATTACK_FRAME_TIME: u16 = 50;
+pub const IDLE_FRAME_TIME: u16 = 250;
+pub const WALK_FRAME_TIME: u16 = 150;
+pub const DEAD_FRAME_TIME: u16 = 1000;
+pub const SCREEN_SIZE: (u32, u32) = (480, 384);
+pub const SAMPLE_RATE: u32 = 22050;
+
+/*
+* Added before bevy's AssetPlugin, which then uses this asset server instead of its own.
+*/
+impl Plugin for PlaceholderAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        let default_io = bevy::asset::create_platform_default_asset_io(app);
+        app.insert_resource(AssetServer::new(PlaceholderAssetIo(default_io)));
+    }
+}
+
+struct PlaceholderAssetIo(Box<dyn AssetIo>);
+
+impl AssetIo for PlaceholderAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            if FORCE_PLACEHOLDERS {
+                if let Some(bytes) = placeholder(path) {
+                    return Ok(bytes);
+                }
+            }
+            match self.0.load_path(path).await {
+                Err(AssetIoError::NotFound(missing)) => match placeholder(path) {
+                    Some(bytes) => {
+                        warn!("{} is missing, using a placeholder", path.display());
+                        Ok(bytes)
+                    }
+                    None => Err(AssetIoError::NotFound(missing)),
+                },
+                result => result,
+            }
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        self.0.read_directory(path)
+    }
+
+    fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        match self.0.get_metadata(path) {
+            Err(AssetIoError::NotFound(_)) if has_placeholder(path) => {
+                Ok(Metadata::new(FileType::File))
+            }
+            result => result,
+        }
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        self.0.watch_path_for_changes(path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.0.watch_for_changes()
+    }
+}
+
+/*
+* What gets generated for which file. Fonts, weapons and credits are in the repository.
+*/
+enum Placeholder {
+    Spritesheet(Rgba<u8>),
+    // The spritesheet's tiles as frames, tagged like the `.anim.ron` clips
+    Aseprite(Rgba<u8>),
+    Arrow,
+    Arena,
+    MenuScreen,
+    Cursor,
+    // Start and end frequency in hertz, length in seconds
+    Tone(f32, f32, f32),
+}
+
+fn placeholder_for(path: &Path) -> Option<Placeholder> {
+    let name = path.file_name()?.to_str()?;
+    let extension = path.extension()?.to_str()?;
+    let in_arrows = path
+        .parent()
+        .and_then(Path::file_name)
+        .map_or(false, |parent| parent == "arrows");
+    match extension {
+        "png" if name.ends_with("_spritesheet.png") => Some(Placeholder::Spritesheet(
+            character_color(name.trim_end_matches("_spritesheet.png")),
+        )),
+        "aseprite" | "ase" => Some(Placeholder::Aseprite(character_color(
+            path.file_stem()?.to_str()?,
+        ))),
+        "png" if in_arrows || name.starts_with("arrow") => Some(Placeholder::Arrow),
+        "png" if name == "arenascreen.png" => Some(Placeholder::Arena),
+        "png" if name == "mainmenuscreen.png" => Some(Placeholder::MenuScreen),
+        "png" if name == "cursor.png" => Some(Placeholder::Cursor),
+        "ogg" | "wav" if name.starts_with("arrow") => Some(Placeholder::Tone(900., 300., 0.12)),
+        "ogg" | "wav" if name.starts_with("footstep") => Some(Placeholder::Tone(180., 90., 0.04)),
+        "ogg" | "wav" if name.starts_with("ui_button") => Some(Placeholder::Tone(660., 660., 0.05)),
+        "ogg" | "wav" => Some(Placeholder::Tone(440., 440., 0.1)),
+        _ => None,
+    }
+}
+
+fn has_placeholder(path: &Path) -> bool {
+    placeholder_for(path).is_some()
+}
+
+fn placeholder(path: &Path) -> Option<Vec<u8>> {
+    let bytes = match placeholder_for(path)? {
+        Placeholder::Spritesheet(color) => encode_png(&spritesheet(color)),
+        Placeholder::Aseprite(color) => character_aseprite(color),
+        Placeholder::Arrow => encode_png(&arrow()),
+        Placeholder::Arena => encode_png(&arena()),
+        Placeholder::MenuScreen => encode_png(&menu_screen()),
+        Placeholder::Cursor => encode_png(&cursor()),
+        Placeholder::Tone(start, end, length) => tone(start, end, length),
+    };
+    Some(bytes)
+}
+
+fn character_color(character: &str) -> Rgba<u8> {
+    match character {
+        "archer" => Rgba([60, 170, 80, 255]),
+        "chaser" => Rgba([200, 60, 50, 255]),
+        "kiting_archer" => Rgba([220, 140, 40, 255]),
+        "turret" => Rgba([130, 130, 150, 255]),
+        // Anything else still gets a stable color of its own
+        _ => {
+            let hash = character
+                .bytes()
+                .fold(7u32, |hash, byte| hash.wrapping_mul(31) + byte as u32);
+            Rgba([
+                80 + (hash % 150) as u8,
+                80 + (hash / 150 % 150) as u8,
+                80 + (hash / 22500 % 150) as u8,
+                255,
+            ])
+        }
+    }
+}
+
+fn encode_png(image: &RgbaImage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .expect("encoding a png in memory can't fail");
+    bytes
+}
+
+/*
+* Images
+*/
+#[derive(Clone, Copy)]
+enum Facing {
+    Down,
+    Left,
+    Right,
+    Up,
+    DownLeft,
+    DownRight,
+    UpLeft,
+    UpRight,
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for px in x..(x + width).min(image.width()) {
+        for py in y..(y + height).min(image.height()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn darken(color: Rgba<u8>, amount: f32) -> Rgba<u8> {
+    let [r, g, b, a] = color.0;
+    Rgba([
+        (r as f32 * amount) as u8,
+        (g as f32 * amount) as u8,
+        (b as f32 * amount) as u8,
+        a,
+    ])
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pose {
+    Idle,
+    Walk,
+    Attack,
+}
+
+// Same tile order as the README: attacks, dead, idles then walks, two frames each per direction.
+// The diagonal attacks, idles and walks follow in the same way.
+fn spritesheet(color: Rgba<u8>) -> RgbaImage {
+    let mut image = RgbaImage::new(TILE_SIZE * TILE_COUNT, TILE_SIZE);
+    let facings = [Facing::Down, Facing::Left, Facing::Right, Facing::Up];
+    let diagonals = [
+        Facing::DownLeft,
+        Facing::DownRight,
+        Facing::UpLeft,
+        Facing::UpRight,
+    ];
+    // First tile of each pose
+    let poses = [
+        (Pose::Attack, 0, facings),
+        (Pose::Idle, 9, facings),
+        (Pose::Walk, 17, facings),
+        (Pose::Attack, 25, diagonals),
+        (Pose::Idle, 33, diagonals),
+        (Pose::Walk, 41, diagonals),
+    ];
+    for (pose, first, facings) in poses {
+        for (index, facing) in facings.iter().enumerate() {
+            let index = index as u32;
+            for frame in 0..2 {
+                let x = (first + index * 2 + frame) * TILE_SIZE;
+                draw_character(&mut image, x, *facing, pose, frame, color);
+            }
+        }
+    }
+    draw_dead(&mut image, 8 * TILE_SIZE, color);
+    image
+}
+
+fn draw_character(
+    image: &mut RgbaImage,
+    x: u32,
+    facing: Facing,
+    pose: Pose,
+    frame: u32,
+    color: Rgba<u8>,
+) {
+    let skin = Rgba([230, 200, 160, 255]);
+    let eye = Rgba([20, 20, 20, 255]);
+    let bow = Rgba([140, 90, 40, 255]);
+    let legs = darken(color, 0.6);
+    // Idle characters bob down a pixel on their second frame
+    let bob = if pose == Pose::Idle { frame } else { 0 };
+    fill(image, x + 3, 1 + bob, 4, 3, skin);
+    fill(image, x + 3, 4 + bob, 4, 4, color);
+    if pose == Pose::Walk {
+        // One leg forward on each frame
+        let (left, right) = if frame == 0 { (1, 0) } else { (0, 1) };
+        fill(image, x + 3 - left, 8, 1, 2, legs);
+        fill(image, x + 6 + right, 8, 1, 2, legs);
+    } else {
+        fill(image, x + 3, 8, 1, 2, legs);
+        fill(image, x + 6, 8, 1, 2, legs);
+    }
+    match facing {
+        Facing::Down => {
+            fill(image, x + 4, 2 + bob, 1, 1, eye);
+            fill(image, x + 5, 2 + bob, 1, 1, eye);
+        }
+        Facing::Left => fill(image, x + 3, 2 + bob, 1, 1, eye),
+        Facing::Right => fill(image, x + 6, 2 + bob, 1, 1, eye),
+        Facing::Up => fill(image, x + 3, 1 + bob, 4, 1, darken(color, 0.6)),
+        Facing::DownLeft => fill(image, x + 3, 2 + bob, 2, 1, eye),
+        Facing::DownRight => fill(image, x + 5, 2 + bob, 2, 1, eye),
+        Facing::UpLeft => fill(image, x + 4, 1 + bob, 3, 1, darken(color, 0.6)),
+        Facing::UpRight => fill(image, x + 3, 1 + bob, 3, 1, darken(color, 0.6)),
+    }
+    if pose == Pose::Attack {
+        // The bow is drawn back on the second frame
+        let pull = frame;
+        match facing {
+            Facing::Down => fill(image, x + 2, 9 - pull, 6, 1, bow),
+            Facing::Left => fill(image, x + 1 + pull, 3, 1, 5, bow),
+            Facing::Right => fill(image, x + 8 - pull, 3, 1, 5, bow),
+            Facing::Up => fill(image, x + 2, pull, 6, 1, bow),
+            // Diagonal bows are drawn in the corner they point to
+            Facing::DownLeft => {
+                fill(image, x + 1 + pull, 6, 1, 3, bow);
+                fill(image, x + 1 + pull, 9 - pull, 3, 1, bow);
+            }
+            Facing::DownRight => {
+                fill(image, x + 8 - pull, 6, 1, 3, bow);
+                fill(image, x + 6 - pull, 9 - pull, 3, 1, bow);
+            }
+            Facing::UpLeft => {
+                fill(image, x + 1 + pull, 1, 1, 3, bow);
+                fill(image, x + 1 + pull, pull, 3, 1, bow);
+            }
+            Facing::UpRight => {
+                fill(image, x + 8 - pull, 1, 1, 3, bow);
+                fill(image, x + 6 - pull, pull, 3, 1, bow);
+            }
+        }
+    }
+}
+
+fn draw_dead(image: &mut RgbaImage, x: u32, color: Rgba<u8>) {
+    fill(image, x + 1, 6, 3, 3, Rgba([230, 200, 160, 255]));
+    fill(image, x + 4, 6, 5, 3, darken(color, 0.7));
+}
+
+// Points right, the game rotates it to the direction of flight.
+fn arrow() -> RgbaImage {
+    let mut image = RgbaImage::new(10, 3);
+    let shaft = Rgba([200, 170, 120, 255]);
+    let head = Rgba([210, 210, 220, 255]);
+    let fletching = Rgba([230, 230, 230, 255]);
+    fill(&mut image, 0, 1, 8, 1, shaft);
+    fill(&mut image, 7, 0, 2, 3, head);
+    fill(&mut image, 9, 1, 1, 1, head);
+    fill(&mut image, 0, 0, 1, 1, fletching);
+    fill(&mut image, 0, 2, 1, 1, fletching);
+    image
+}
+
+// Checkered floor inside the map bounds, walls around it.
+fn arena() -> RgbaImage {
+    let (width, height) = SCREEN_SIZE;
+    let mut image = RgbaImage::new(width, height);
+    let wall = Rgba([60, 50, 60, 255]);
+    let floor = [Rgba([110, 95, 80, 255]), Rgba([100, 86, 72, 255])];
+    // The image is centered on the world origin and characters are 20 pixels tall.
+    let left = (width as f32 / 2. + MAP_LEFT_BOUND - 10.) as u32;
+    let right = (width as f32 / 2. + MAP_RIGHT_BOUND + 10.) as u32;
+    let top = (height as f32 / 2. - MAP_UP_BOUND - 10.) as u32;
+    let bottom = (height as f32 / 2. - MAP_DOWN_BOUND + 10.) as u32;
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        *pixel = if x < left || x >= right || y < top || y >= bottom {
+            wall
+        } else {
+            floor[((x / 16 + y / 16) % 2) as usize]
+        };
+    }
+    image
+}
+
+fn menu_screen() -> RgbaImage {
+    let (width, height) = SCREEN_SIZE;
+    let mut image = RgbaImage::new(width, height);
+    for (_, y, pixel) in image.enumerate_pixels_mut() {
+        let shade = 20 + (y * 40 / height) as u8;
+        *pixel = Rgba([shade, shade / 3, shade / 2, 255]);
+    }
+    image
+}
+
+fn cursor() -> RgbaImage {
+    let mut image = RgbaImage::new(32, 32);
+    let color = Rgba([180, 32, 42, 255]);
+    // A diagonal arrow with its point at the top left corner
+    for i in 0..12 {
+        fill(&mut image, 0, i, i + 1, 1, color);
+    }
+    for i in 0..10 {
+        fill(&mut image, 6 + i, 6 + i, 3, 3, color);
+    }
+    image
+}
+
+/*
+* Aseprite
+*/
+
+// Tag over the frames `from` to `to`, both included.
+pub struct AsepriteTag<'a> {
+    pub name: &'a str,
+    pub from: u16,
+    pub to: u16,
+    // 0 forward, 1 reverse, 2 ping pong
+    pub direction: u8,
+}
+
+// Splits the spritesheet into one frame per tile, with a tag for every clip of the README layout.
+fn character_aseprite(color: Rgba<u8>) -> Vec<u8> {
+    let sheet = spritesheet(color);
+    let mut durations = vec![DEAD_FRAME_TIME; TILE_COUNT as usize];
+    let mut names = vec![("dead".to_string(), 8)];
+    let sides = ["down", "left", "right", "up"];
+    let diagonals = ["downleft", "downright", "upleft", "upright"];
+    // First tile of each pose
+    let poses = [
+        ("attack", 0, ATTACK_FRAME_TIME, sides),
+        ("idle", 9, IDLE_FRAME_TIME, sides),
+        ("walk", 17, WALK_FRAME_TIME, sides),
+        ("attack", 25, ATTACK_FRAME_TIME, diagonals),
+        ("idle", 33, IDLE_FRAME_TIME, diagonals),
+        ("walk", 41, WALK_FRAME_TIME, diagonals),
+    ];
+    for (state, first, duration, directions) in poses {
+        for (index, direction) in directions.iter().enumerate() {
+            let from = first + index as u16 * 2;
+            durations[from as usize] = duration;
+            durations[from as usize + 1] = duration;
+            names.push((format!("{}_{}", state, direction), from));
+        }
+    }
+    let mut tags: Vec<AsepriteTag> = names
+        .iter()
+        .map(|(name, from)| AsepriteTag {
+            name,
+            from: *from,
+            to: if name == "dead" { *from } else { from + 1 },
+            direction: 0,
+        })
+        .collect();
+    // Arrows leave on the second attack frame, every walk frame is a step.
+    for (name, from) in &names {
+        if name.starts_with("attack") {
+            tags.push(AsepriteTag {
+                name: "event:release",
+                from: from + 1,
+                to: from + 1,
+                direction: 0,
+            });
+        }
+    }
+    for (from, to) in [(17, 24), (41, 48)] {
+        tags.push(AsepriteTag {
+            name: "event:footstep",
+            from,
+            to,
+            direction: 0,
+        });
+    }
+    let frames: Vec<(RgbaImage, u16)> = durations
+        .iter()
+        .enumerate()
+        .map(|(tile, duration)| {
+            let x = tile as u32 * TILE_SIZE;
+            let tile = imageops::crop_imm(&sheet, x, 0, TILE_SIZE, TILE_SIZE).to_image();
+            (tile, *duration)
+        })
+        .collect();
+    encode_aseprite(&frames, &tags)
+}
+
+/*
+* 32 bit RGBA file with one layer, each frame is an uncompressed cel covering the whole canvas.
+* Frames are `(image, duration in milliseconds)` and all have the size of the first one.
+*/
+pub fn encode_aseprite(frames: &[(RgbaImage, u16)], tags: &[AsepriteTag]) -> Vec<u8> {
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |(image, _)| (image.width(), image.height()));
+
+    let mut bytes = Vec::new();
+    // Header, the file size is filled in at the end
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&0xA5E0u16.to_le_bytes());
+    bytes.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(width as u16).to_le_bytes());
+    bytes.extend_from_slice(&(height as u16).to_le_bytes());
+    // Color depth, then flags: layer opacity is valid
+    bytes.extend_from_slice(&32u16.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    // Deprecated speed and two reserved dwords
+    bytes.extend_from_slice(&100u16.to_le_bytes());
+    bytes.extend_from_slice(&[0; 8]);
+    // Transparent index, 3 ignored bytes and the color count
+    bytes.extend_from_slice(&[0; 6]);
+    // Pixel ratio 1:1, grid at 0,0 of 16x16
+    bytes.extend_from_slice(&[1, 1]);
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.resize(128, 0);
+
+    for (index, (image, duration)) in frames.iter().enumerate() {
+        let mut chunks: Vec<(u16, Vec<u8>)> = Vec::new();
+        if index == 0 {
+            let mut layer = Vec::new();
+            // Visible and editable, normal layer at the root
+            layer.extend_from_slice(&3u16.to_le_bytes());
+            layer.extend_from_slice(&[0; 10]);
+            layer.push(255);
+            layer.extend_from_slice(&[0; 3]);
+            push_aseprite_string(&mut layer, "Layer 1");
+            chunks.push((0x2004, layer));
+
+            let mut tag_chunk = Vec::new();
+            tag_chunk.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+            tag_chunk.extend_from_slice(&[0; 8]);
+            for tag in tags {
+                tag_chunk.extend_from_slice(&tag.from.to_le_bytes());
+                tag_chunk.extend_from_slice(&tag.to.to_le_bytes());
+                tag_chunk.push(tag.direction);
+                // Repeat count, reserved bytes, color and an extra byte
+                tag_chunk.extend_from_slice(&[0; 12]);
+                push_aseprite_string(&mut tag_chunk, tag.name);
+            }
+            chunks.push((0x2018, tag_chunk));
+        }
+        let mut cel = Vec::new();
+        // Layer 0 at 0,0, fully opaque
+        cel.extend_from_slice(&[0; 6]);
+        cel.push(255);
+        // Raw cel, then the z-index and reserved bytes
+        cel.extend_from_slice(&0u16.to_le_bytes());
+        cel.extend_from_slice(&[0; 7]);
+        cel.extend_from_slice(&(image.width() as u16).to_le_bytes());
+        cel.extend_from_slice(&(image.height() as u16).to_le_bytes());
+        cel.extend_from_slice(image.as_raw());
+        chunks.push((0x2005, cel));
+
+        let size: usize = 16 + chunks.iter().map(|(_, data)| 6 + data.len()).sum::<usize>();
+        bytes.extend_from_slice(&(size as u32).to_le_bytes());
+        bytes.extend_from_slice(&0xF1FAu16.to_le_bytes());
+        bytes.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&duration.to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        for (chunk_type, data) in chunks {
+            bytes.extend_from_slice(&(6 + data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&chunk_type.to_le_bytes());
+            bytes.extend_from_slice(&data);
+        }
+    }
+    let file_size = bytes.len() as u32;
+    bytes[..4].copy_from_slice(&file_size.to_le_bytes());
+    bytes
+}
+
+fn push_aseprite_string(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend_from_slice(&(text.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+/*
+* Sounds
+*/
+
+// 16 bit mono wav of a square wave sliding between two frequencies and fading out.
+fn tone(start: f32, end: f32, length: f32) -> Vec<u8> {
+    let sample_count = (SAMPLE_RATE as f32 * length) as u32;
+    let mut samples = Vec::with_capacity(sample_count as usize);
+    let mut phase = 0f32;
+    for i in 0..sample_count {
+        let progress = i as f32 / sample_count as f32;
+        let frequency = start + (end - start) * progress;
+        phase = (phase + frequency / SAMPLE_RATE as f32).fract();
+        let square = if phase < 0.5 { 1. } else { -1. };
+        let volume = 0.3 * (1. - progress);
+        samples.push((square * volume * i16::MAX as f32) as i16);
+    }
+
+    let data_size = sample_count * 2;
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
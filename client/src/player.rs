@@ -1,27 +1,41 @@
-use bevy::{prelude::*, time::FixedTimestep};
+use std::time::Duration;
+
+use bevy::{ecs::system::IntoChainSystem, prelude::*, time::FixedTimestep};
 
 use crate::{
+    actions::{Action, ActionState},
+    animation::{animate_sprite, AnimationEvent, AnimationState, FOOTSTEP_EVENT, RELEASE_EVENT},
     assets::GameAssets,
     components::*,
-    game::{MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
-    projectiles::ArcherArrow,
+    game::{run_if_in_game, OnlyInGame, MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
+    particles::{ParticleEvent, FOOTSTEP_DUST},
+    projectiles::{ArcherArrow, ProjectileMask},
+    settings::{play_sfx, Settings},
+    weapons::WeaponDefinition,
     GameState, TIME_STEP,
 };
 pub struct PlayerPlugin;
-pub const ARCHER_PROJECTILE_SPEED: f32 = 500.;
 pub const PLAYERSPEED: f32 = 100.;
-// IN SECONDS
-pub const ATTACK_ANIM_SPEED: f32 = 0.10;
-pub const IDLE_ANIM_SPEED: f32 = 0.25;
+pub const ARCHER_COLLIDER_SIZE: Vec2 = Vec2::new(12., 16.);
+pub const ARCHER_MAX_HEALTH: f32 = 10.;
+// IN SECONDS, the archer can't be hit again for this long after taking damage.
+pub const ARCHER_INVULNERABILITY_TIME: f32 = 1.;
+pub const ARCHER_KNOCKBACK_SPEED: f32 = 150.;
+// How far the right stick has to be pushed before the player fires.
+pub const STICK_FIRE_THRESHOLD: f32 = 0.5;
+// From the center of the sprite down to its feet, in world units.
+pub const ARCHER_FEET_OFFSET: Vec2 = Vec2::new(0., -8.);
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(init_player))
             .add_system_set(
-                SystemSet::on_update(GameState::InGame)
-                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
                     .with_system(player_move)
-                    .with_system(player_attack),
+                    .with_system(player_attack.before(animate_sprite))
+                    .with_system(player_release_arrow.after(animate_sprite))
+                    .with_system(player_footsteps.after(animate_sprite)),
             );
     }
 }
@@ -46,72 +60,83 @@ fn init_player(mut commands: Commands, game_assets: Res<GameAssets>) {
             ..default()
         })
         .insert(Player)
+        .insert(OnlyInGame)
+        .insert(Collider(ARCHER_COLLIDER_SIZE))
+        .insert(Team(ProjectileMask::Player))
+        .insert(Health::new(ARCHER_MAX_HEALTH))
+        .insert(HitReaction::new(
+            ARCHER_INVULNERABILITY_TIME,
+            ARCHER_KNOCKBACK_SPEED,
+            0.,
+        ))
         .insert(SpriteDirection(ESpriteDirection::default()))
+        .insert(Aim::default())
+        .insert(Velocity(Vec2::ZERO))
         .insert(CharacterState((
             ECharacterMovementState::default(),
             ECharacterAttackState::default(),
         )))
-        .insert(AnimationTimer(Timer::from_seconds(IDLE_ANIM_SPEED, true)))
-        .insert(AttackTimer(Timer::from_seconds(ATTACK_ANIM_SPEED, true)));
+        .insert(Animator::new(game_assets.archer_animations.clone()))
+        // Duration is taken from the weapon once it fires.
+        .insert(AttackTimer(Timer::from_seconds(1.0, true)));
 }
 
 fn player_move(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Transform, &mut CharacterState), With<Player>>,
+    actions: Res<ActionState>,
+    mut query: Query<
+        (&mut Transform, &mut CharacterState, &mut Velocity),
+        (With<Player>, Without<Dead>),
+    >,
     time: Res<Time>,
 ) {
-    for (mut transform, mut character_state) in &mut query {
-        let mut move_input: Vec2 = Vec2::ZERO;
-        if keyboard_input.pressed(KeyCode::W) {
-            move_input.y = 1.;
-        }
-        if keyboard_input.pressed(KeyCode::S) {
-            move_input.y = -1.;
-        }
-        if keyboard_input.pressed(KeyCode::A) {
-            move_input.x = -1.;
-        }
-        if keyboard_input.pressed(KeyCode::D) {
-            move_input.x = 1.;
-        }
-        if move_input.x == 0.0 && move_input.y == 0.0 {
-            if character_state.0 .0 != ECharacterMovementState::IDLE {
-                character_state.0 .0 = ECharacterMovementState::IDLE;
-            }
-        } else if move_input.x == 1.0 {
-            if character_state.0 .0 != ECharacterMovementState::WALK_RIGHT {
-                character_state.0 .0 = ECharacterMovementState::WALK_RIGHT;
-            }
-        } else if move_input.x == -1.0 {
-            if character_state.0 .0 != ECharacterMovementState::WALK_LEFT {
-                character_state.0 .0 = ECharacterMovementState::WALK_LEFT;
-            }
-        } else if move_input.y == 1.0 {
-            if character_state.0 .0 != ECharacterMovementState::WALK_UP {
-                character_state.0 .0 = ECharacterMovementState::WALK_UP;
-            }
-        } else if move_input.y == -1.0 {
-            if character_state.0 .0 != ECharacterMovementState::WALK_DOWN {
-                character_state.0 .0 = ECharacterMovementState::WALK_DOWN;
-            }
+    for (mut transform, mut character_state, mut velocity) in &mut query {
+        // The left stick's magnitude scales the speed.
+        let move_input = actions.movement();
+        let movement_state = ECharacterMovementState::from_direction(move_input);
+        if character_state.0 .0 != movement_state {
+            character_state.0 .0 = movement_state;
         }
         let mut new_player_position =
             transform.translation + move_input.extend(0.) * PLAYERSPEED * time.delta_seconds();
         new_player_position.x = new_player_position.x.clamp(MAP_LEFT_BOUND, MAP_RIGHT_BOUND);
         new_player_position.y = new_player_position.y.clamp(MAP_DOWN_BOUND, MAP_UP_BOUND);
+        if time.delta_seconds() > 0. {
+            velocity.0 =
+                (new_player_position - transform.translation).truncate() / time.delta_seconds();
+        }
         transform.translation = new_player_position;
     }
 }
 
 fn player_attack(
-    mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&Transform, &mut AttackTimer, &mut CharacterState), With<Player>>,
+    actions: Res<ActionState>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut query: Query<
+        (
+            &Transform,
+            &mut AttackTimer,
+            &mut CharacterState,
+            &mut Aim,
+            &mut Animator,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
     time: Res<Time>,
     game_assets: Res<GameAssets>,
-    audio: Res<Audio>
+    weapons: Res<Assets<WeaponDefinition>>,
 ) {
-    for (transform, mut timer, mut character_state) in &mut query {
+    let weapon = match weapons.get(&game_assets.archer_bow) {
+        Some(weapon) => weapon,
+        None => return,
+    };
+    let cursor_position = camera_query
+        .get_single()
+        .ok()
+        .and_then(|(camera, camera_transform)| {
+            cursor_world_position(&windows, camera, camera_transform)
+        });
+    for (transform, mut timer, mut character_state, mut aim, mut animator) in &mut query {
         if character_state.0 .1 != ECharacterAttackState::IDLE {
             timer.tick(time.delta());
         }
@@ -120,61 +145,101 @@ fn player_attack(
             character_state.0 .1 = ECharacterAttackState::IDLE;
             timer.reset();
         }
-        //Means it can attack
-        if character_state.0 .1 == ECharacterAttackState::IDLE {
-            let mut should_attack = false;
-            // Up Right
-            if keyboard_input.pressed(KeyCode::Up) && keyboard_input.pressed(KeyCode::Right) {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_UPRIGHT;
-                should_attack = true;
-            }
-            // Down Right
-            else if keyboard_input.pressed(KeyCode::Down)
-                && keyboard_input.pressed(KeyCode::Right)
-            {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_DOWNRIGHT;
-                should_attack = true;
-            }
-            // Down Left
-            else if keyboard_input.pressed(KeyCode::Down) && keyboard_input.pressed(KeyCode::Left)
-            {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_DOWNLEFT;
-                should_attack = true;
-            }
-            // Up Left
-            else if keyboard_input.pressed(KeyCode::Up) && keyboard_input.pressed(KeyCode::Left) {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_UPLEFT;
-                should_attack = true;
-            }
-            // Up
-            else if keyboard_input.pressed(KeyCode::Up) {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_UP;
-                should_attack = true;
-            }
-            // Right
-            else if keyboard_input.pressed(KeyCode::Right) {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_RIGHT;
-                should_attack = true;
-            }
-            // Down
-            else if keyboard_input.pressed(KeyCode::Down) {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_DOWN;
-                should_attack = true;
-            }
-            // Left
-            else if keyboard_input.pressed(KeyCode::Left) {
-                character_state.0 .1 = ECharacterAttackState::ATTACK_LEFT;
-                should_attack = true;
-            }
-            if should_attack {
-                commands.spawn_bundle(ArcherArrow::new(
-                    ARCHER_PROJECTILE_SPEED,
-                    &transform.translation,
-                    &character_state.0 .1,
-                    &game_assets.archer_arrows,
-                ));
-                audio.play(game_assets.arrow_noise.clone());
+        // Fire actions or the right stick aim and fire, otherwise aim at the cursor.
+        let stick_aim = actions.aim_axis;
+        let key_aim = actions.fire_direction();
+        let mut should_attack = false;
+        if key_aim != Vec2::ZERO {
+            aim.0 = key_aim.normalize();
+            should_attack = true;
+        } else if stick_aim.length() >= STICK_FIRE_THRESHOLD {
+            aim.0 = stick_aim.normalize();
+            should_attack = true;
+        } else if let Some(cursor_position) = cursor_position {
+            let to_cursor = cursor_position - transform.translation.truncate();
+            if to_cursor != Vec2::ZERO {
+                aim.0 = to_cursor.normalize();
             }
+            should_attack = actions.pressed(Action::FireAtCursor);
+        }
+        //Means it can attack
+        if character_state.0 .1 == ECharacterAttackState::IDLE && should_attack {
+            // Face whichever sprite direction is closest to the aim.
+            character_state.0 .1 = ECharacterAttackState::from_direction(aim.0);
+            // The attack state lasts as long as the weapon cooldown.
+            timer.set_duration(Duration::from_secs_f32(weapon.fire_cooldown));
+            // The arrow is fired on the release frame of the attack clip.
+            animator.request(AnimationState::Attack);
+        }
+    }
+}
+
+fn player_release_arrow(
+    mut commands: Commands,
+    mut animation_events: EventReader<AnimationEvent>,
+    query: Query<(&Transform, &Aim), (With<Player>, Without<Dead>)>,
+    game_assets: Res<GameAssets>,
+    weapons: Res<Assets<WeaponDefinition>>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+) {
+    let weapon = match weapons.get(&game_assets.archer_bow) {
+        Some(weapon) => weapon,
+        None => return,
+    };
+    for event in animation_events.iter() {
+        if event.tag != RELEASE_EVENT {
+            continue;
+        }
+        if let Ok((transform, aim)) = query.get(event.entity) {
+            commands.spawn_bundle(ArcherArrow::new(
+                weapon,
+                &transform.translation,
+                aim.0,
+                event.entity,
+                ProjectileMask::Player,
+            ));
+            play_sfx(&audio, weapon.sound.clone(), &settings);
+        }
+    }
+}
+
+fn player_footsteps(
+    mut animation_events: EventReader<AnimationEvent>,
+    query: Query<(&Transform, &Velocity), With<Player>>,
+    mut particle_events: EventWriter<ParticleEvent>,
+    game_assets: Res<GameAssets>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+) {
+    for event in animation_events.iter() {
+        if event.tag != FOOTSTEP_EVENT {
+            continue;
+        }
+        if let Ok((transform, velocity)) = query.get(event.entity) {
+            play_sfx(&audio, game_assets.footstep.clone(), &settings);
+            // Dust kicked up behind the player
+            particle_events.send(ParticleEvent {
+                effect: &FOOTSTEP_DUST,
+                position: transform.translation.truncate() + ARCHER_FEET_OFFSET,
+                direction: -velocity.0,
+            });
         }
     }
 }
+
+/*
+* Converts the cursor position on the primary window into world space.
+*/
+fn cursor_world_position(
+    windows: &Windows,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let screen_position = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+    let ndc = (screen_position / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+    Some(ndc_to_world.project_point3(ndc.extend(-1.0)).truncate())
+}
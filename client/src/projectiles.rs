@@ -1,20 +1,40 @@
-use bevy::{prelude::*, time::FixedTimestep};
+use bevy::{
+    ecs::system::IntoChainSystem, prelude::*, sprite::collide_aabb::collide, time::FixedTimestep,
+};
 
 use crate::{
-    components::{Collider, ECharacterAttackState, Projectile, Velocity},
-    game::{MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
-    GameState, TIME_STEP,
+    components::{Collider, Damage, Dead, Projectile, Shooter, Team, Velocity},
+    game::{run_if_in_game, OnlyInGame, MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
+    particles::{ParticleEvent, ARROW_IMPACT, ARROW_TRAIL},
+    weapons::WeaponDefinition,
+    TIME_STEP,
 };
 
 /**
  * CONSTANTS
  */
+pub const ARROW_COLLIDER_SIZE: Vec2 = Vec2::new(6., 6.);
 
 // If projectile mask is player, it will not effect player. (Visa-versa for enemy)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProjectileMask {
     Player,
     Enemy,
 }
+
+/*
+* Sent when a projectile overlaps a character of the other team.
+*/
+pub struct ProjectileHitEvent {
+    pub shooter: Entity,
+    pub target: Entity,
+    pub point: Vec2,
+    pub damage: f32,
+    pub critical: bool,
+    // Normalized direction the projectile was flying in.
+    pub direction: Vec2,
+}
+
 #[derive(Bundle)]
 pub struct ArcherArrow {
     #[bundle]
@@ -22,68 +42,33 @@ pub struct ArcherArrow {
     velocity: Velocity,
     collider: Collider,
     projectile: Projectile,
+    shooter: Shooter,
+    damage: Damage,
+    only_in_game: OnlyInGame,
 }
 
 impl ArcherArrow {
     /**
-     * Let
+     * Arrow fired by `shooter` towards `direction` with the speed, damage, spread and sprite
+     * of the weapon. The sprite points right and is rotated to match the velocity.
      */
     pub fn new(
-        speed: f32,
+        weapon: &WeaponDefinition,
         location: &Vec3,
-        arrow_direction: &ECharacterAttackState,
-        arrow_handle_images: &Vec<Handle<Image>>,
+        direction: Vec2,
+        shooter: Entity,
+        mask: ProjectileMask,
     ) -> ArcherArrow {
-        let a_velocity: Vec2;
-        let arrow_texture: Handle<Image>;
-        let flip_x: bool;
-        match arrow_direction {
-            ECharacterAttackState::ATTACK_UP => {
-                a_velocity = Vec2 { x: 0.0, y: 1.0 } * speed;
-                arrow_texture = arrow_handle_images.get(2).unwrap().clone();
-                flip_x = false;
-            }
-            ECharacterAttackState::ATTACK_UPRIGHT => {
-                a_velocity = Vec2 { x: 0.66, y: 0.66 } * speed;
-                arrow_texture = arrow_handle_images.get(1).unwrap().clone();
-                flip_x = true;
-            }
-            ECharacterAttackState::ATTACK_UPLEFT => {
-                a_velocity = Vec2 { x: -0.66, y: 0.66 } * speed;
-                arrow_texture = arrow_handle_images.get(1).unwrap().clone();
-                flip_x = false;
-            }
-            ECharacterAttackState::ATTACK_RIGHT => {
-                a_velocity = Vec2 { x: 1.0, y: 0.0 } * speed;
-                arrow_texture = arrow_handle_images.get(0).unwrap().clone();
-                flip_x = false;
-            }
-            ECharacterAttackState::ATTACK_DOWN => {
-                a_velocity = Vec2 { x: 0.0, y: -1.0 } * speed;
-                arrow_texture = arrow_handle_images.get(2).unwrap().clone();
-                flip_x = false;
-            }
-            ECharacterAttackState::ATTACK_DOWNRIGHT => {
-                a_velocity = Vec2 { x: 0.6, y: -0.6 } * speed;
-                arrow_texture = arrow_handle_images.get(1).unwrap().clone();
-                flip_x = false;
-            }
-            ECharacterAttackState::ATTACK_DOWNLEFT => {
-                a_velocity = Vec2 { x: -0.6, y: -0.6 } * speed;
-                arrow_texture = arrow_handle_images.get(1).unwrap().clone();
-                flip_x = true;
-            }
-            ECharacterAttackState::ATTACK_LEFT => {
-                a_velocity = Vec2 { x: -1.0, y: 0.0 } * speed;
-                arrow_texture = arrow_handle_images.get(0).unwrap().clone();
-                flip_x = false;
-            }
-            ECharacterAttackState::IDLE => {
-               /* It should never get here. */
-               a_velocity = Vec2 { x: -1.0, y: 0.0 } * speed;
-               arrow_texture = arrow_handle_images.get(0).unwrap().clone();
-               flip_x = false;
-            }
+        // Random offset inside the weapon's spread cone.
+        let spread_angle = weapon.spread.to_radians() * (rand::random::<f32>() - 0.5);
+        let a_velocity = Mat2::from_angle(spread_angle)
+            * direction.try_normalize().unwrap_or(Vec2::X)
+            * weapon.projectile_speed;
+        let critical = rand::random::<f32>() < weapon.crit_chance;
+        let damage = if critical {
+            weapon.damage * weapon.crit_multiplier
+        } else {
+            weapon.damage
         };
         ArcherArrow {
             sprite_bundle: SpriteBundle {
@@ -93,23 +78,25 @@ impl ArcherArrow {
                         y: location.y,
                         z: location.z - 0.1,
                     },
+                    rotation: Quat::from_rotation_z(a_velocity.y.atan2(a_velocity.x)),
                     scale: Vec3 {
                         x: 2.0,
                         y: 2.0,
                         z: 2.0,
                     },
-                    ..default()
                 },
-                sprite: Sprite {
-                    flip_x,
-                    ..default()
-                },
-                texture: arrow_texture,
+                texture: weapon.sprite.clone(),
                 ..default()
             },
-            collider: Collider,
+            collider: Collider(ARROW_COLLIDER_SIZE),
             velocity: Velocity(a_velocity),
-            projectile: Projectile(ProjectileMask::Player),
+            projectile: Projectile(mask),
+            shooter: Shooter(shooter),
+            damage: Damage {
+                amount: damage,
+                critical,
+            },
+            only_in_game: OnlyInGame,
         }
     }
 }
@@ -118,9 +105,9 @@ pub struct ProjectilePlugin;
 
 impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
+        app.add_event::<ProjectileHitEvent>().add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
                 .with_system(move_projectiles)
                 .with_system(check_projectile_collisions),
         );
@@ -129,27 +116,170 @@ impl Plugin for ProjectilePlugin {
 
 fn move_projectiles(
     mut query: Query<(&mut Transform, &Velocity), With<Projectile>>,
+    mut particle_events: EventWriter<ParticleEvent>,
     time: Res<Time>,
 ) {
     for (mut projectile_transform, projectile_velocity) in &mut query {
         projectile_transform.translation += projectile_velocity.0.extend(0.) * time.delta_seconds();
+        particle_events.send(ParticleEvent {
+            effect: &ARROW_TRAIL,
+            position: projectile_transform.translation.truncate(),
+            direction: -projectile_velocity.0,
+        });
     }
 }
 
 fn check_projectile_collisions(
-    mut query: Query<(Entity, &Transform), With<Projectile>>,
+    projectile_query: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &Collider,
+        &Projectile,
+        &Shooter,
+        &Damage,
+    )>,
+    target_query: Query<
+        (Entity, &Transform, &Collider, &Team),
+        (Without<Projectile>, Without<Dead>),
+    >,
+    mut hit_events: EventWriter<ProjectileHitEvent>,
+    mut particle_events: EventWriter<ParticleEvent>,
     mut commands: Commands,
 ) {
-    // Kill projectiles that are on or over border of map.
-    for (entity, projectile_transform) in &mut query {
+    for (
+        entity,
+        projectile_transform,
+        projectile_velocity,
+        projectile_collider,
+        projectile,
+        shooter,
+        damage,
+    ) in &projectile_query
+    {
+        // Sparks fly back the way the arrow came from.
+        let impact = |position: Vec2| ParticleEvent {
+            effect: &ARROW_IMPACT,
+            position,
+            direction: -projectile_velocity.0,
+        };
+        // Kill projectiles that are on or over border of map.
         if projectile_transform.translation.x >= MAP_RIGHT_BOUND
             || projectile_transform.translation.x <= MAP_LEFT_BOUND
         {
+            particle_events.send(impact(projectile_transform.translation.truncate()));
             commands.entity(entity).despawn_recursive();
+            continue;
         } else if projectile_transform.translation.y >= MAP_UP_BOUND
             || projectile_transform.translation.y <= MAP_DOWN_BOUND
         {
+            particle_events.send(impact(projectile_transform.translation.truncate()));
             commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        for (target, target_transform, target_collider, team) in &target_query {
+            // Never hit our own side
+            if team.0 == projectile.0 {
+                continue;
+            }
+            if collide(
+                projectile_transform.translation,
+                projectile_collider.0,
+                target_transform.translation,
+                target_collider.0,
+            )
+            .is_some()
+            {
+                // Impact point is the projectile position pulled onto the target box.
+                let center = target_transform.translation.truncate();
+                let half_size = target_collider.0 / 2.;
+                let point = projectile_transform
+                    .translation
+                    .truncate()
+                    .clamp(center - half_size, center + half_size);
+                hit_events.send(ProjectileHitEvent {
+                    shooter: shooter.0,
+                    target,
+                    point,
+                    damage: damage.amount,
+                    critical: damage.critical,
+                    direction: projectile_velocity.0.normalize_or_zero(),
+                });
+                particle_events.send(impact(point));
+                commands.entity(entity).despawn_recursive();
+                // A projectile only hits one target.
+                break;
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET_SIZE: Vec2 = Vec2::new(8., 8.);
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Events<ProjectileHitEvent>>();
+        world.init_resource::<Events<ParticleEvent>>();
+        world
+    }
+
+    fn spawn_target(world: &mut World, position: Vec2, mask: ProjectileMask) -> Entity {
+        world
+            .spawn()
+            .insert(Transform::from_translation(position.extend(0.)))
+            .insert(Collider(TARGET_SIZE))
+            .insert(Team(mask))
+            .id()
+    }
+
+    fn spawn_arrow(world: &mut World, position: Vec2, mask: ProjectileMask) -> Entity {
+        let shooter = world.spawn().id();
+        world
+            .spawn()
+            .insert(Transform::from_translation(position.extend(0.)))
+            .insert(Velocity(Vec2::X))
+            .insert(Collider(ARROW_COLLIDER_SIZE))
+            .insert(Projectile(mask))
+            .insert(Shooter(shooter))
+            .insert(Damage {
+                amount: 1.,
+                critical: false,
+            })
+            .id()
+    }
+
+    fn hit_targets(world: &mut World) -> Vec<Entity> {
+        SystemStage::single(check_projectile_collisions).run(world);
+        let events = world.resource::<Events<ProjectileHitEvent>>();
+        let mut reader = events.get_reader();
+        reader.iter(events).map(|hit| hit.target).collect()
+    }
+
+    #[test]
+    fn player_arrow_hits_the_enemy_it_overlaps() {
+        let mut world = world();
+        let player = spawn_target(&mut world, Vec2::ZERO, ProjectileMask::Player);
+        let enemy = spawn_target(&mut world, Vec2::ZERO, ProjectileMask::Enemy);
+        let arrow = spawn_arrow(&mut world, Vec2::ZERO, ProjectileMask::Player);
+
+        let targets = hit_targets(&mut world);
+
+        assert_eq!(targets, vec![enemy]);
+        assert!(!targets.contains(&player));
+        assert!(world.get_entity(arrow).is_none());
+    }
+
+    #[test]
+    fn player_arrow_passes_through_players() {
+        let mut world = world();
+        spawn_target(&mut world, Vec2::ZERO, ProjectileMask::Player);
+        let arrow = spawn_arrow(&mut world, Vec2::ZERO, ProjectileMask::Player);
+
+        assert!(hit_targets(&mut world).is_empty());
+        assert!(world.get_entity(arrow).is_some());
+    }
+}
@@ -0,0 +1,491 @@
+/**
+ * Audio, video and gameplay options. Loaded from settings.ron before the window is created,
+ * applied live while the settings menu is open and saved when it closes.
+ */
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Action, ActionState, Binding, InputBindings, PendingRebind},
+    assets::MainMenuAssets,
+    config::{load_config, save_config},
+    ui::{
+        utils::basic_text,
+        widgets::{
+            spawn_button, spawn_option_button, spawn_slider, spawn_toggle, WidgetEvent,
+            WidgetLabel, WidgetSystem, DIALOG_LAYER, LIGHTCOLOR,
+        },
+    },
+};
+
+pub struct SettingsPlugin;
+
+pub const SETTINGS_FILE: &str = "settings.ron";
+// Logical size of the window, the window scale multiplies it.
+pub const WINDOW_WIDTH: f32 = 480.;
+pub const WINDOW_HEIGHT: f32 = 384.;
+pub const MAX_WINDOW_SCALE: f32 = 4.;
+// Slider ranges as (min, max, step), loaded settings are clamped to them too.
+pub const VOLUME_RANGE: (f32, f32, f32) = (0., 1., 0.1);
+pub const WINDOW_SCALE_RANGE: (f32, f32, f32) = (1., MAX_WINDOW_SCALE, 1.);
+pub const SCREEN_SHAKE_RANGE: (f32, f32, f32) = (0., 1., 0.25);
+// Actions that can be rebound from the controls menu, menu actions keep their defaults.
+pub const REBINDABLE_ACTIONS: [(&str, Action); 8] = [
+    ("Move up", Action::MoveUp),
+    ("Move down", Action::MoveDown),
+    ("Move left", Action::MoveLeft),
+    ("Move right", Action::MoveRight),
+    ("Fire up", Action::FireUp),
+    ("Fire down", Action::FireDown),
+    ("Fire left", Action::FireLeft),
+    ("Fire right", Action::FireRight),
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub window_scale: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    // 0 turns screen shake off.
+    pub screen_shake: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            master_volume: 1.,
+            sfx_volume: 1.,
+            window_scale: 1.,
+            fullscreen: false,
+            vsync: true,
+            screen_shake: 1.,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        load_config::<Settings>(SETTINGS_FILE)
+            .unwrap_or_default()
+            .clamped()
+    }
+
+    // Hand edited files can hold anything, a scale of 0 or NaN breaks the window.
+    fn clamped(self) -> Settings {
+        let default = Settings::default();
+        Settings {
+            master_volume: clamp_to(self.master_volume, VOLUME_RANGE, default.master_volume),
+            sfx_volume: clamp_to(self.sfx_volume, VOLUME_RANGE, default.sfx_volume),
+            window_scale: clamp_to(self.window_scale, WINDOW_SCALE_RANGE, default.window_scale)
+                .round(),
+            screen_shake: clamp_to(self.screen_shake, SCREEN_SHAKE_RANGE, default.screen_shake),
+            ..self
+        }
+    }
+
+    pub fn save(&self) {
+        save_config(SETTINGS_FILE, self);
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::Fifo
+        } else {
+            PresentMode::Immediate
+        }
+    }
+
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        WindowDescriptor {
+            title: "2d_shooter".to_string(),
+            width: WINDOW_WIDTH,
+            height: WINDOW_HEIGHT,
+            resizable: false,
+            // Scales everything up while the game still sees a 480x384 window
+            scale_factor_override: Some(self.window_scale as f64),
+            mode: self.window_mode(),
+            present_mode: self.present_mode(),
+            ..default()
+        }
+    }
+}
+
+// Values that aren't numbers fall back to the default.
+fn clamp_to(value: f32, (min, max, _): (f32, f32, f32), default: f32) -> f32 {
+    if value.is_nan() {
+        default
+    } else {
+        value.clamp(min, max)
+    }
+}
+
+// Plays a sound effect at the volume from the settings.
+pub fn play_sfx(audio: &Audio, sound: Handle<AudioSource>, settings: &Settings) {
+    audio.play_with_settings(
+        sound,
+        PlaybackSettings::ONCE.with_volume(settings.sfx_volume()),
+    );
+}
+
+/*
+* Root of the settings menu. Screens that open it insert their own cleanup marker on it.
+*/
+#[derive(Component)]
+pub struct SettingsMenu;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Component)]
+enum SettingsWidget {
+    MasterVolume,
+    SfxVolume,
+    WindowScale,
+    Fullscreen,
+    Vsync,
+    ScreenShake,
+    Controls,
+    Back,
+    Rebind(Action),
+    ControlsBack,
+}
+
+/*
+* Controls menu, opened over the settings menu like a dialog.
+*/
+#[derive(Component)]
+struct ControlsMenu;
+
+// Sent once the settings menu was closed with Back, so the screen under it can come back.
+pub struct SettingsClosed;
+
+/*
+* Screens that open the settings menu order their systems after this label.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct SettingsSystem;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SettingsClosed>()
+            .add_system(
+                settings_menu_controls
+                    .label(SettingsSystem)
+                    .after(WidgetSystem),
+            )
+            .add_system(update_rebind_labels.after(SettingsSystem))
+            .add_system(apply_window_settings.after(SettingsSystem))
+            // Despawns are only seen by later stages
+            .add_system_to_stage(CoreStage::PostUpdate, save_settings);
+    }
+}
+
+pub fn spawn_settings_menu(
+    commands: &mut Commands,
+    settings: &Settings,
+    font: Handle<Font>,
+) -> Entity {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0., 0., 0., 0.8).into(),
+            ..Default::default()
+        })
+        .insert(SettingsMenu)
+        .with_children(|root| {
+            root.spawn_bundle(basic_text(
+                "Settings",
+                32.,
+                font.clone(),
+                None,
+                Some(10.),
+                None,
+                LIGHTCOLOR,
+            ));
+            spawn_slider(
+                root,
+                "Master volume",
+                settings.master_volume,
+                VOLUME_RANGE,
+                font.clone(),
+                SettingsWidget::MasterVolume,
+            );
+            spawn_slider(
+                root,
+                "Sfx volume",
+                settings.sfx_volume,
+                VOLUME_RANGE,
+                font.clone(),
+                SettingsWidget::SfxVolume,
+            );
+            spawn_slider(
+                root,
+                "Window scale",
+                settings.window_scale,
+                WINDOW_SCALE_RANGE,
+                font.clone(),
+                SettingsWidget::WindowScale,
+            );
+            spawn_toggle(
+                root,
+                "Fullscreen",
+                settings.fullscreen,
+                font.clone(),
+                SettingsWidget::Fullscreen,
+            );
+            spawn_toggle(
+                root,
+                "Vsync",
+                settings.vsync,
+                font.clone(),
+                SettingsWidget::Vsync,
+            );
+            spawn_slider(
+                root,
+                "Screen shake",
+                settings.screen_shake,
+                SCREEN_SHAKE_RANGE,
+                font.clone(),
+                SettingsWidget::ScreenShake,
+            );
+            spawn_option_button(root, "Controls", font.clone(), 0, SettingsWidget::Controls);
+            spawn_button(root, "Back", font, 0, SettingsWidget::Back);
+        })
+        .id()
+}
+
+/*
+* One button per rebindable action, pressing one waits for the next key or button.
+*/
+fn spawn_controls_menu(commands: &mut Commands, menu: Entity, font: Handle<Font>) {
+    let controls = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            // Blocks the mouse from reaching the settings under it
+            color: Color::rgba(0., 0., 0., 0.9).into(),
+            ..Default::default()
+        })
+        .insert(ControlsMenu)
+        .id();
+    // Spawned last so it is drawn over the settings
+    commands.entity(menu).add_child(controls);
+    commands.entity(controls).with_children(|root| {
+        root.spawn_bundle(basic_text(
+            "Controls",
+            32.,
+            font.clone(),
+            None,
+            Some(10.),
+            None,
+            LIGHTCOLOR,
+        ));
+        for (label, action) in REBINDABLE_ACTIONS {
+            // The label is filled in with the binding by update_rebind_labels
+            spawn_option_button(
+                root,
+                label,
+                font.clone(),
+                DIALOG_LAYER,
+                SettingsWidget::Rebind(action),
+            );
+        }
+        spawn_button(
+            root,
+            "Back",
+            font,
+            DIALOG_LAYER,
+            SettingsWidget::ControlsBack,
+        );
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn settings_menu_controls(
+    mut commands: Commands,
+    mut actions: ResMut<ActionState>,
+    mut settings: ResMut<Settings>,
+    mut pending: ResMut<PendingRebind>,
+    mut widget_events: EventReader<WidgetEvent>,
+    mut closed_events: EventWriter<SettingsClosed>,
+    widget_query: Query<&SettingsWidget>,
+    menu_query: Query<Entity, With<SettingsMenu>>,
+    controls_query: Query<Entity, With<ControlsMenu>>,
+    mm_assets: Res<MainMenuAssets>,
+) {
+    let menu = match menu_query.get_single() {
+        Ok(menu) => menu,
+        Err(_) => return,
+    };
+    let controls = controls_query.get_single().ok();
+    let mut close = false;
+    let mut close_controls = false;
+    for event in widget_events.iter() {
+        match event {
+            WidgetEvent::Pressed(entity) => match widget_query.get(*entity) {
+                Ok(SettingsWidget::Back) => close = true,
+                Ok(SettingsWidget::Controls) if controls.is_none() => {
+                    spawn_controls_menu(&mut commands, menu, mm_assets.alagrad.clone());
+                }
+                Ok(SettingsWidget::Rebind(action)) => pending.0 = Some(*action),
+                Ok(SettingsWidget::ControlsBack) => close_controls = true,
+                _ => {}
+            },
+            WidgetEvent::Toggled(entity, on) => match widget_query.get(*entity) {
+                Ok(SettingsWidget::Fullscreen) => settings.fullscreen = *on,
+                Ok(SettingsWidget::Vsync) => settings.vsync = *on,
+                _ => {}
+            },
+            WidgetEvent::SliderChanged(entity, value) => match widget_query.get(*entity) {
+                Ok(SettingsWidget::MasterVolume) => settings.master_volume = *value,
+                Ok(SettingsWidget::SfxVolume) => settings.sfx_volume = *value,
+                Ok(SettingsWidget::WindowScale) => settings.window_scale = value.round(),
+                Ok(SettingsWidget::ScreenShake) => settings.screen_shake = *value,
+                _ => {}
+            },
+        }
+    }
+    if actions.just_released(Action::MenuBack) {
+        actions.consume(Action::MenuBack);
+        if controls.is_some() {
+            close_controls = true;
+        } else {
+            close = true;
+        }
+    }
+    if close_controls || close {
+        pending.0 = None;
+    }
+    if close {
+        commands.entity(menu).despawn_recursive();
+        closed_events.send(SettingsClosed);
+    } else if close_controls {
+        if let Some(controls) = controls {
+            commands.entity(controls).despawn_recursive();
+        }
+    }
+}
+
+// Shows the keyboard or mouse binding of each action, or that a key is being waited for.
+fn update_rebind_labels(
+    bindings: Res<InputBindings>,
+    pending: Res<PendingRebind>,
+    mut widget_query: Query<(&SettingsWidget, &mut WidgetLabel)>,
+    added_query: Query<(), Added<ControlsMenu>>,
+) {
+    if !bindings.is_changed() && !pending.is_changed() && added_query.is_empty() {
+        return;
+    }
+    for (widget, mut label) in &mut widget_query {
+        let action = match widget {
+            SettingsWidget::Rebind(action) => *action,
+            _ => continue,
+        };
+        let name = REBINDABLE_ACTIONS
+            .iter()
+            .find(|(_, rebindable)| *rebindable == action)
+            .map_or("", |(name, _)| *name);
+        let value = if pending.0 == Some(action) {
+            "press a key".to_string()
+        } else {
+            bindings
+                .get(action)
+                .iter()
+                .find(|binding| !matches!(binding, Binding::Gamepad(_)))
+                .map_or("none".to_string(), Binding::name)
+        };
+        let text = format!("{}: {}", name, value);
+        if label.0 != text {
+            label.0 = text;
+        }
+    }
+}
+
+fn apply_window_settings(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+    let scale = Some(settings.window_scale as f64);
+    if window.scale_factor_override() != scale {
+        window.set_scale_factor_override(scale);
+        window.set_resolution(WINDOW_WIDTH, WINDOW_HEIGHT);
+    }
+    if window.mode() != settings.window_mode() {
+        window.set_mode(settings.window_mode());
+    }
+    if window.present_mode() != settings.present_mode() {
+        window.set_present_mode(settings.present_mode());
+    }
+}
+
+// Saved once the menu is gone, however it was closed.
+fn save_settings(settings: Res<Settings>, closed_menus: RemovedComponents<SettingsMenu>) {
+    if closed_menus.iter().next().is_some() {
+        settings.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_settings_are_clamped_to_the_sliders() {
+        let settings = Settings {
+            master_volume: -1.,
+            sfx_volume: 3.,
+            window_scale: 0.,
+            screen_shake: f32::INFINITY,
+            ..default()
+        }
+        .clamped();
+        assert_eq!(settings.master_volume, 0.);
+        assert_eq!(settings.sfx_volume, 1.);
+        assert_eq!(settings.window_scale, 1.);
+        assert_eq!(settings.screen_shake, 1.);
+        let settings = Settings {
+            window_scale: f32::NAN,
+            ..default()
+        }
+        .clamped();
+        assert_eq!(settings.window_scale, 1.);
+        let settings = Settings {
+            window_scale: 2.6,
+            ..default()
+        }
+        .clamped();
+        assert_eq!(settings.window_scale, 3.);
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{components::Player, health::DamageEvent, settings::Settings, GameState};
+
+pub struct ScreenShakePlugin;
+
+// Offset of the camera in pixels at full trauma and full screen shake setting.
+pub const MAX_SHAKE_OFFSET: f32 = 6.;
+// Trauma lost per second.
+pub const SHAKE_DECAY: f32 = 2.;
+// Trauma added every time the player is hit.
+pub const PLAYER_HIT_TRAUMA: f32 = 0.5;
+// Trauma added when one of the player's arrows lands, just enough to feel the hit.
+pub const PLAYER_SHOT_TRAUMA: f32 = 0.1;
+
+/*
+* Between 0 and 1, the camera shakes with the square of it.
+*/
+#[derive(Default)]
+pub struct ScreenShake {
+    pub trauma: f32,
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+}
+
+impl Plugin for ScreenShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenShake>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(shake_on_player_hit)
+                    .with_system(shake_camera.after(shake_on_player_hit)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(reset_camera));
+    }
+}
+
+fn shake_on_player_hit(
+    mut damage_events: EventReader<DamageEvent>,
+    player_query: Query<(), With<Player>>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    for event in damage_events.iter() {
+        if player_query.get(event.target).is_ok() {
+            shake.add_trauma(PLAYER_HIT_TRAUMA);
+        } else if player_query.get(event.source).is_ok() {
+            shake.add_trauma(PLAYER_SHOT_TRAUMA);
+        }
+    }
+}
+
+fn shake_camera(
+    mut shake: ResMut<ScreenShake>,
+    settings: Res<Settings>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    time: Res<Time>,
+) {
+    if shake.trauma <= 0. {
+        return;
+    }
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.);
+    let strength = MAX_SHAKE_OFFSET * settings.screen_shake * shake.trauma * shake.trauma;
+    let mut rng = rand::thread_rng();
+    for mut transform in &mut camera_query {
+        // The camera always sits at the origin otherwise
+        transform.translation.x = rng.gen_range(-1.0..=1.0) * strength;
+        transform.translation.y = rng.gen_range(-1.0..=1.0) * strength;
+    }
+}
+
+fn reset_camera(
+    mut shake: ResMut<ScreenShake>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    shake.trauma = 0.;
+    for mut transform in &mut camera_query {
+        transform.translation.x = 0.;
+        transform.translation.y = 0.;
+    }
+}
@@ -0,0 +1,87 @@
+/**
+ * Replaces the system cursor with mainmenu/cursor.png once the assets are loaded.
+ */
+use bevy::{prelude::*, transform::TransformSystem, ui::FocusPolicy};
+
+use crate::{assets::MainMenuAssets, GameState};
+
+pub struct CursorPlugin;
+
+pub const CURSOR_SIZE: Vec2 = Vec2::new(32., 32.);
+// UI nodes are stacked 0.001 apart from 0, this stays above any menu.
+pub const CURSOR_Z: f32 = 100.;
+
+#[derive(Component)]
+struct GameCursor;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_exit(GameState::AssetLoading).with_system(spawn_cursor))
+            .add_system(move_cursor)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                raise_cursor.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn spawn_cursor(
+    mut commands: Commands,
+    mm_assets: Res<MainMenuAssets>,
+    mut windows: ResMut<Windows>,
+) {
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_cursor_visibility(false);
+    }
+    commands
+        .spawn_bundle(ImageBundle {
+            image: UiImage(mm_assets.cursor.clone()),
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Px(CURSOR_SIZE.x), Val::Px(CURSOR_SIZE.y)),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        // Clicks go through to the widgets under it
+        .insert(FocusPolicy::Pass)
+        .insert(GameCursor);
+}
+
+fn move_cursor(
+    windows: Res<Windows>,
+    mut cursor_query: Query<(&mut Style, &mut Visibility), With<GameCursor>>,
+) {
+    let position = windows
+        .get_primary()
+        .and_then(|window| window.cursor_position());
+    for (mut style, mut visibility) in &mut cursor_query {
+        // Hidden while the mouse is outside of the window
+        if visibility.is_visible != position.is_some() {
+            visibility.is_visible = position.is_some();
+        }
+        if let Some(position) = position {
+            // The hotspot is the top left corner of the image
+            style.position = UiRect {
+                left: Val::Px(position.x),
+                bottom: Val::Px(position.y - CURSOR_SIZE.y),
+                ..Default::default()
+            };
+        }
+    }
+}
+
+/*
+* Root nodes are stacked in query order, so menus spawned after the cursor would be drawn
+* over it. Its final depth is set once transforms are propagated, for the UI render to use.
+*/
+fn raise_cursor(mut cursor_query: Query<&mut GlobalTransform, With<GameCursor>>) {
+    for mut global_transform in &mut cursor_query {
+        let mut transform = global_transform.compute_transform();
+        if transform.translation.z != CURSOR_Z {
+            transform.translation.z = CURSOR_Z;
+            *global_transform = transform.into();
+        }
+    }
+}
@@ -0,0 +1,266 @@
+/**
+ * In-game HUD. Health, score and wave are rewritten from the gameplay events that change them,
+ * the cooldown bar follows the attack timer while it ticks.
+ */
+use bevy::prelude::*;
+
+use crate::{
+    assets::MainMenuAssets,
+    components::{AttackTimer, CharacterState, ECharacterAttackState, Health, Player},
+    game::{OnlyInGame, ScoreEvent},
+    health::{DamageEvent, DeathEvent},
+    player::ARCHER_MAX_HEALTH,
+    ui::{
+        utils::basic_text,
+        widgets::{DARKCOLOR, LIGHTCOLOR},
+    },
+    wave::{WaveClearedEvent, WaveStartedEvent},
+    GameState,
+};
+
+pub struct HudPlugin;
+
+pub const HUD_MARGIN: f32 = 8.;
+pub const HEALTH_BAR_SIZE: Vec2 = Vec2::new(100., 8.);
+pub const COOLDOWN_BAR_SIZE: Vec2 = Vec2::new(60., 4.);
+pub const BAR_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.6);
+pub const COOLDOWN_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+/*
+* Structs to get the different HUD fields
+*/
+#[derive(Component)]
+struct HealthText;
+
+#[derive(Component)]
+struct HealthBarFill;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct WaveText;
+
+#[derive(Component)]
+struct CooldownBarFill;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_hud))
+            // Not paused with the fixed timestep systems, nothing changes while paused anyway.
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(update_health)
+                    .with_system(update_score)
+                    .with_system(update_wave)
+                    .with_system(update_cooldown),
+            );
+    }
+}
+
+fn spawn_hud(mut commands: Commands, mm_assets: Res<MainMenuAssets>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(HUD_MARGIN)),
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(OnlyInGame)
+        .with_children(|root| {
+            // Top row: health on the left, wave in the middle, score on the right
+            root.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Auto),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexStart,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .with_children(|top| {
+                top.spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|health| {
+                    health
+                        .spawn_bundle(basic_text(
+                            &health_label(ARCHER_MAX_HEALTH, ARCHER_MAX_HEALTH),
+                            12.,
+                            mm_assets.alagrad.clone(),
+                            None,
+                            Some(4.),
+                            None,
+                            LIGHTCOLOR,
+                        ))
+                        .insert(HealthText);
+                    spawn_bar(health, HEALTH_BAR_SIZE, LIGHTCOLOR, HealthBarFill);
+                });
+                top.spawn_bundle(basic_text(
+                    "Get ready",
+                    12.,
+                    mm_assets.alagrad.clone(),
+                    None,
+                    None,
+                    None,
+                    LIGHTCOLOR,
+                ))
+                .insert(WaveText);
+                top.spawn_bundle(basic_text(
+                    "Score 0",
+                    12.,
+                    mm_assets.alagrad.clone(),
+                    None,
+                    None,
+                    None,
+                    LIGHTCOLOR,
+                ))
+                .insert(ScoreText);
+            });
+            // Bottom row: fire cooldown, full when the bow is ready
+            root.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Auto),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .with_children(|bottom| {
+                spawn_bar(bottom, COOLDOWN_BAR_SIZE, COOLDOWN_COLOR, CooldownBarFill);
+            });
+        });
+}
+
+/*
+* A background node with a fill whose width is set in percent.
+*/
+fn spawn_bar<T: Component>(parent: &mut ChildBuilder, size: Vec2, color: Color, marker: T) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(size.x), Val::Px(size.y)),
+                ..Default::default()
+            },
+            color: BAR_BACKGROUND.into(),
+            ..Default::default()
+        })
+        .with_children(|bar| {
+            bar.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    ..Default::default()
+                },
+                color: color.into(),
+                ..Default::default()
+            })
+            .insert(marker);
+        });
+}
+
+fn health_label(current: f32, max: f32) -> String {
+    format!("HP {}/{}", current.ceil(), max)
+}
+
+fn update_health(
+    mut damage_events: EventReader<DamageEvent>,
+    mut death_events: EventReader<DeathEvent>,
+    player_query: Query<&Health, With<Player>>,
+    mut text_query: Query<&mut Text, With<HealthText>>,
+    mut bar_query: Query<(&mut Style, &mut UiColor), With<HealthBarFill>>,
+) {
+    let mut player_hit = false;
+    for event in damage_events.iter() {
+        player_hit |= player_query.get(event.target).is_ok();
+    }
+    for event in death_events.iter() {
+        player_hit |= player_query.get(event.entity).is_ok();
+    }
+    if !player_hit {
+        return;
+    }
+    let health = match player_query.get_single() {
+        Ok(health) => health,
+        Err(_) => return,
+    };
+    let ratio = (health.current / health.max).clamp(0., 1.);
+    for mut text in &mut text_query {
+        text.sections[0].value = health_label(health.current, health.max);
+    }
+    for (mut style, mut color) in &mut bar_query {
+        style.size.width = Val::Percent(ratio * 100.);
+        // Darker once the player is on their last hits
+        *color = if ratio <= 0.3 { DARKCOLOR } else { LIGHTCOLOR }.into();
+    }
+}
+
+fn update_score(
+    mut score_events: EventReader<ScoreEvent>,
+    mut text_query: Query<&mut Text, With<ScoreText>>,
+) {
+    let total = match score_events.iter().last() {
+        Some(event) => event.total,
+        None => return,
+    };
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Score {}", total);
+    }
+}
+
+fn update_wave(
+    mut started_events: EventReader<WaveStartedEvent>,
+    mut cleared_events: EventReader<WaveClearedEvent>,
+    mut text_query: Query<&mut Text, With<WaveText>>,
+) {
+    // Both can be sent in the same frame, the wave that just started wins.
+    let mut value = None;
+    for event in cleared_events.iter() {
+        value = Some(format!("Wave {} cleared", event.wave));
+    }
+    for event in started_events.iter() {
+        value = Some(format!("Wave {}", event.wave));
+    }
+    if let Some(value) = value {
+        for mut text in &mut text_query {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+fn update_cooldown(
+    player_query: Query<
+        (&AttackTimer, &CharacterState),
+        (
+            With<Player>,
+            Or<(Changed<AttackTimer>, Changed<CharacterState>)>,
+        ),
+    >,
+    mut bar_query: Query<&mut Style, With<CooldownBarFill>>,
+) {
+    let (timer, character_state) = match player_query.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    // The attack state lasts as long as the cooldown, so idle means ready to fire.
+    let ready = if character_state.0 .1 == ECharacterAttackState::IDLE {
+        1.
+    } else {
+        timer.0.percent()
+    };
+    for mut style in &mut bar_query {
+        style.size.width = Val::Percent(ready * 100.);
+    }
+}
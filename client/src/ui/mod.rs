@@ -1 +1,4 @@
-pub mod utils;
\ No newline at end of file
+pub mod cursor;
+pub mod hud;
+pub mod utils;
+pub mod widgets;
@@ -0,0 +1,643 @@
+/**
+ * Menu widgets: buttons, button lists, sliders, toggles and confirm dialogs.
+ * Focus moves between widgets with the menu actions (keyboard and gamepad) or by hovering them
+ * with the mouse. Screens tag their widgets with their own components and read `WidgetEvent`s.
+ */
+use bevy::{ecs::system::EntityCommands, prelude::*, ui::FocusPolicy};
+
+use crate::{
+    actions::{Action, ActionState},
+    assets::MainMenuAssets,
+    settings::{play_sfx, Settings},
+    ui::utils::basic_text,
+};
+
+pub struct WidgetPlugin;
+
+pub const DARKCOLOR: Color = Color::rgb(115. / 255., 23. / 255., 45. / 255.);
+pub const LIGHTCOLOR: Color = Color::rgb(180. / 255., 32. / 255., 42. / 255.);
+pub const WIDGET_FONT_SIZE: f32 = 24.;
+pub const WIDGET_MARGIN: f32 = 20.;
+// Toggles and sliders come in longer lists of options, so they are smaller.
+pub const OPTION_FONT_SIZE: f32 = 16.;
+pub const OPTION_MARGIN: f32 = 8.;
+pub const SLIDER_TRACK_SIZE: Vec2 = Vec2::new(120., 8.);
+pub const SLIDER_TRACK_COLOR: Color = Color::rgba(0., 0., 0., 0.6);
+// Confirm dialogs sit above every other widget.
+pub const DIALOG_LAYER: u8 = 1;
+
+/*
+* Anything that can take focus. Only widgets of the highest layer on screen can be focused,
+* so a dialog takes the focus away from the menu under it.
+*/
+#[derive(Component, Default, Clone, Copy)]
+pub struct Focusable {
+    pub layer: u8,
+}
+
+/*
+* The widget that the menu actions currently act on.
+*/
+#[derive(Default)]
+pub struct Focus(pub Option<Entity>);
+
+#[derive(Component, Debug, Clone, PartialEq)]
+pub enum Widget {
+    Button,
+    Toggle {
+        on: bool,
+    },
+    Slider {
+        value: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    },
+}
+
+// Text shown on the widget, toggles append their value.
+#[derive(Component)]
+pub struct WidgetLabel(pub String);
+
+#[derive(Component)]
+struct SliderTrack;
+
+#[derive(Component)]
+struct SliderFill;
+
+/*
+* Sent for the widget entity when it is used.
+*/
+pub enum WidgetEvent {
+    Pressed(Entity),
+    Toggled(Entity, bool),
+    SliderChanged(Entity, f32),
+}
+
+/*
+* A yes/no dialog, identified by the id it was spawned with.
+*/
+#[derive(Component)]
+pub struct ConfirmDialog(pub &'static str);
+
+#[derive(Component, Clone, Copy)]
+struct DialogButton {
+    dialog: Entity,
+    confirm: bool,
+}
+
+pub struct DialogClosed {
+    pub id: &'static str,
+    pub confirmed: bool,
+}
+
+/*
+* Screens order their own systems after this label to see this frame's widget events.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct WidgetSystem;
+
+impl Plugin for WidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Focus>()
+            .add_event::<WidgetEvent>()
+            .add_event::<DialogClosed>()
+            .add_system_set(
+                SystemSet::new()
+                    .label(WidgetSystem)
+                    .with_system(mouse_focus)
+                    .with_system(keyboard_navigation.after(mouse_focus))
+                    .with_system(mouse_activation.after(keyboard_navigation))
+                    .with_system(slider_drag.after(mouse_activation))
+                    .with_system(dialog_controls.after(slider_drag))
+                    .with_system(update_widget_visuals.after(dialog_controls)),
+            );
+    }
+}
+
+/*
+* Spawning
+*/
+pub fn spawn_button<T: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    font: Handle<Font>,
+    layer: u8,
+    tag: T,
+) -> Entity {
+    spawn_widget(
+        parent,
+        Widget::Button,
+        label,
+        font,
+        (WIDGET_FONT_SIZE, WIDGET_MARGIN),
+        layer,
+    )
+    .insert(tag)
+    .id()
+}
+
+// Button sized like toggles and sliders, for longer lists.
+pub fn spawn_option_button<T: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    font: Handle<Font>,
+    layer: u8,
+    tag: T,
+) -> Entity {
+    spawn_widget(
+        parent,
+        Widget::Button,
+        label,
+        font,
+        (OPTION_FONT_SIZE, OPTION_MARGIN),
+        layer,
+    )
+    .insert(tag)
+    .id()
+}
+
+// One button per item, top to bottom.
+pub fn spawn_button_list<T: Component + Copy>(
+    parent: &mut ChildBuilder,
+    items: &[(&str, T)],
+    font: Handle<Font>,
+    layer: u8,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..Default::default()
+        })
+        .with_children(|list| {
+            for (label, tag) in items {
+                spawn_button(list, label, font.clone(), layer, *tag);
+            }
+        });
+}
+
+pub fn spawn_toggle<T: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    on: bool,
+    font: Handle<Font>,
+    tag: T,
+) -> Entity {
+    spawn_widget(
+        parent,
+        Widget::Toggle { on },
+        label,
+        font,
+        (OPTION_FONT_SIZE, OPTION_MARGIN),
+        0,
+    )
+    .insert(tag)
+    .id()
+}
+
+pub fn spawn_slider<T: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    value: f32,
+    (min, max, step): (f32, f32, f32),
+    font: Handle<Font>,
+    tag: T,
+) -> Entity {
+    let widget = Widget::Slider {
+        value: value.clamp(min, max),
+        min,
+        max,
+        step,
+    };
+    spawn_widget(
+        parent,
+        widget,
+        label,
+        font,
+        (OPTION_FONT_SIZE, OPTION_MARGIN),
+        0,
+    )
+    .insert(tag)
+    .with_children(|slider| {
+        slider
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(SLIDER_TRACK_SIZE.x), Val::Px(SLIDER_TRACK_SIZE.y)),
+                    margin: UiRect {
+                        left: Val::Px(10.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                color: SLIDER_TRACK_COLOR.into(),
+                ..Default::default()
+            })
+            .insert(Interaction::default())
+            .insert(SliderTrack)
+            .with_children(|track| {
+                track
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                            ..Default::default()
+                        },
+                        color: DARKCOLOR.into(),
+                        focus_policy: FocusPolicy::Pass,
+                        ..Default::default()
+                    })
+                    .insert(SliderFill);
+            });
+    })
+    .id()
+}
+
+/*
+* Yes/no dialog covering `parent`, which should be the full screen root of the menu.
+*/
+pub fn spawn_confirm_dialog(
+    commands: &mut Commands,
+    parent: Entity,
+    id: &'static str,
+    message: &str,
+    font: Handle<Font>,
+) -> Entity {
+    let dialog = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            // Blocks the mouse from reaching the widgets under it
+            color: Color::rgba(0., 0., 0., 0.8).into(),
+            ..Default::default()
+        })
+        .insert(ConfirmDialog(id))
+        .id();
+    // Spawned last so it is drawn over the menu
+    commands.entity(parent).add_child(dialog);
+    commands.entity(dialog).with_children(|root| {
+        root.spawn_bundle(basic_text(
+            message,
+            16.,
+            font.clone(),
+            None,
+            Some(10.),
+            None,
+            LIGHTCOLOR,
+        ));
+        spawn_button(
+            root,
+            "Yes",
+            font.clone(),
+            DIALOG_LAYER,
+            DialogButton {
+                dialog,
+                confirm: true,
+            },
+        );
+        spawn_button(
+            root,
+            "No",
+            font,
+            DIALOG_LAYER,
+            DialogButton {
+                dialog,
+                confirm: false,
+            },
+        );
+    });
+    dialog
+}
+
+fn spawn_widget<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    widget: Widget,
+    label: &str,
+    font: Handle<Font>,
+    (font_size, margin): (f32, f32),
+    layer: u8,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut entity = parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(margin),
+                ..Default::default()
+            },
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        color: Color::NONE.into(),
+        ..Default::default()
+    });
+    entity
+        .insert(widget)
+        .insert(WidgetLabel(label.to_string()))
+        .insert(Focusable { layer })
+        .with_children(|button| {
+            button
+                .spawn_bundle(basic_text(
+                    label, font_size, font, None, None, None, DARKCOLOR,
+                ))
+                .insert(FocusPolicy::Pass);
+        });
+    entity
+}
+
+/*
+* Focus
+*/
+fn top_layer<'a>(focusables: impl Iterator<Item = &'a Focusable>) -> Option<u8> {
+    focusables.map(|focusable| focusable.layer).max()
+}
+
+fn mouse_focus(
+    mut focus: ResMut<Focus>,
+    widget_query: Query<(Entity, &Interaction, &Focusable), Changed<Interaction>>,
+    track_query: Query<(&Interaction, &Parent), (With<SliderTrack>, Changed<Interaction>)>,
+    focusable_query: Query<&Focusable>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+    mm_assets: Option<Res<MainMenuAssets>>,
+) {
+    let top = top_layer(focusable_query.iter());
+    let hovered = widget_query
+        .iter()
+        .filter(|(_, interaction, focusable)| {
+            **interaction != Interaction::None && Some(focusable.layer) == top
+        })
+        .map(|(entity, _, _)| entity)
+        .chain(
+            track_query
+                .iter()
+                .filter(|(interaction, _)| **interaction != Interaction::None)
+                .map(|(_, parent)| parent.get()),
+        )
+        .next();
+    if let Some(entity) = hovered {
+        if focus.0 != Some(entity) {
+            focus.0 = Some(entity);
+            if let Some(mm_assets) = mm_assets {
+                play_sfx(&audio, mm_assets.ui_button.clone(), &settings);
+            }
+        }
+    }
+}
+
+fn keyboard_navigation(
+    mut actions: ResMut<ActionState>,
+    mut focus: ResMut<Focus>,
+    mut widget_query: Query<(Entity, &Focusable, &Node, &GlobalTransform, &mut Widget)>,
+    mut widget_events: EventWriter<WidgetEvent>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+    mm_assets: Option<Res<MainMenuAssets>>,
+) {
+    let top = top_layer(widget_query.iter().map(|(_, focusable, _, _, _)| focusable));
+    // Reading order, top to bottom then left to right. Skipped until the layout has run.
+    let mut order: Vec<(Entity, Vec3)> = widget_query
+        .iter()
+        .filter(|(_, focusable, node, _, _)| {
+            Some(focusable.layer) == top && node.size != Vec2::ZERO
+        })
+        .map(|(entity, _, _, transform, _)| (entity, transform.translation()))
+        .collect();
+    if order.is_empty() {
+        return;
+    }
+    order.sort_by(|(_, a), (_, b)| {
+        (-a.y.round(), a.x.round())
+            .partial_cmp(&(-b.y.round(), b.x.round()))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let order: Vec<Entity> = order.into_iter().map(|(entity, _)| entity).collect();
+    let mut index = focus
+        .0
+        .and_then(|entity| order.iter().position(|e| *e == entity))
+        .unwrap_or(0);
+
+    let play_sound = || {
+        if let Some(mm_assets) = &mm_assets {
+            play_sfx(&audio, mm_assets.ui_button.clone(), &settings);
+        }
+    };
+    if actions.just_released(Action::MenuDown) {
+        index = (index + 1) % order.len();
+        play_sound();
+    }
+    if actions.just_released(Action::MenuUp) {
+        index = (index + order.len() - 1) % order.len();
+        play_sound();
+    }
+    let focused = order[index];
+    if focus.0 != Some(focused) {
+        focus.0 = Some(focused);
+    }
+
+    let (_, _, _, _, mut widget) = match widget_query.get_mut(focused) {
+        Ok(widget) => widget,
+        Err(_) => return,
+    };
+    let mut direction = 0.;
+    if actions.just_released(Action::MenuLeft) {
+        direction -= 1.;
+    }
+    if actions.just_released(Action::MenuRight) {
+        direction += 1.;
+    }
+    if direction != 0. {
+        match &mut *widget {
+            Widget::Slider {
+                value,
+                min,
+                max,
+                step,
+            } => {
+                let new_value = (*value + *step * direction).clamp(*min, *max);
+                if new_value != *value {
+                    *value = new_value;
+                    widget_events.send(WidgetEvent::SliderChanged(focused, new_value));
+                    play_sound();
+                }
+            }
+            Widget::Toggle { on } => {
+                *on = !*on;
+                widget_events.send(WidgetEvent::Toggled(focused, *on));
+                play_sound();
+            }
+            Widget::Button => {}
+        }
+    }
+    if actions.just_released(Action::MenuConfirm) {
+        actions.consume(Action::MenuConfirm);
+        activate(focused, &mut widget, &mut widget_events);
+    }
+}
+
+fn mouse_activation(
+    mut widget_query: Query<(Entity, &Interaction, &Focusable, &mut Widget), Changed<Interaction>>,
+    focusable_query: Query<&Focusable>,
+    mut widget_events: EventWriter<WidgetEvent>,
+) {
+    let top = top_layer(focusable_query.iter());
+    for (entity, interaction, focusable, mut widget) in &mut widget_query {
+        if *interaction == Interaction::Clicked && Some(focusable.layer) == top {
+            activate(entity, &mut widget, &mut widget_events);
+        }
+    }
+}
+
+// Sliders aren't pressed, they are changed from their track.
+fn activate(entity: Entity, widget: &mut Widget, widget_events: &mut EventWriter<WidgetEvent>) {
+    match widget {
+        Widget::Button => widget_events.send(WidgetEvent::Pressed(entity)),
+        Widget::Toggle { on } => {
+            *on = !*on;
+            widget_events.send(WidgetEvent::Toggled(entity, *on));
+        }
+        Widget::Slider { .. } => {}
+    }
+}
+
+/*
+* Sets the slider value from the cursor while the track is held.
+*/
+fn slider_drag(
+    windows: Res<Windows>,
+    track_query: Query<(&Interaction, &Node, &GlobalTransform, &Parent), With<SliderTrack>>,
+    mut widget_query: Query<&mut Widget>,
+    mut widget_events: EventWriter<WidgetEvent>,
+) {
+    let cursor = match windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    for (interaction, node, transform, parent) in &track_query {
+        if *interaction != Interaction::Clicked || node.size.x <= 0. {
+            continue;
+        }
+        let left = transform.translation().x - node.size.x / 2.;
+        let ratio = ((cursor.x - left) / node.size.x).clamp(0., 1.);
+        if let Ok(mut widget) = widget_query.get_mut(parent.get()) {
+            if let Widget::Slider {
+                value,
+                min,
+                max,
+                step,
+            } = &mut *widget
+            {
+                let mut new_value = *min + (*max - *min) * ratio;
+                if *step > 0. {
+                    new_value = (*min + ((new_value - *min) / *step).round() * *step).min(*max);
+                }
+                if new_value != *value {
+                    *value = new_value;
+                    widget_events.send(WidgetEvent::SliderChanged(parent.get(), new_value));
+                }
+            }
+        }
+    }
+}
+
+fn dialog_controls(
+    mut commands: Commands,
+    mut actions: ResMut<ActionState>,
+    mut widget_events: EventReader<WidgetEvent>,
+    mut closed_events: EventWriter<DialogClosed>,
+    button_query: Query<&DialogButton>,
+    dialog_query: Query<(Entity, &ConfirmDialog)>,
+) {
+    let mut answers: Vec<(Entity, bool)> = widget_events
+        .iter()
+        .filter_map(|event| match event {
+            WidgetEvent::Pressed(entity) => button_query.get(*entity).ok(),
+            _ => None,
+        })
+        .map(|button| (button.dialog, button.confirm))
+        .collect();
+    // Backing out of a dialog is the same as answering no.
+    if actions.just_released(Action::MenuBack) {
+        if let Some((dialog, _)) = dialog_query.iter().next() {
+            actions.consume(Action::MenuBack);
+            answers.push((dialog, false));
+        }
+    }
+    for (dialog, confirmed) in answers {
+        if let Ok((_, tag)) = dialog_query.get(dialog) {
+            closed_events.send(DialogClosed {
+                id: tag.0,
+                confirmed,
+            });
+            commands.entity(dialog).despawn_recursive();
+        }
+    }
+}
+
+/*
+* Highlights the focused widget and shows the current values.
+*/
+fn update_widget_visuals(
+    focus: Res<Focus>,
+    widget_query: Query<(Entity, &Widget, &WidgetLabel, &Children)>,
+    changed_query: Query<(), Or<(Changed<Widget>, Changed<WidgetLabel>, Added<Focusable>)>>,
+    mut text_query: Query<&mut Text>,
+    track_query: Query<&Children, With<SliderTrack>>,
+    mut fill_query: Query<(&mut Style, &mut UiColor), With<SliderFill>>,
+) {
+    if !focus.is_changed() && changed_query.is_empty() {
+        return;
+    }
+    for (entity, widget, label, children) in &widget_query {
+        let color = if focus.0 == Some(entity) {
+            LIGHTCOLOR
+        } else {
+            DARKCOLOR
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].style.color = color;
+                match widget {
+                    Widget::Toggle { on } => {
+                        text.sections[0].value =
+                            format!("{}: {}", label.0, if *on { "On" } else { "Off" });
+                    }
+                    Widget::Button if text.sections[0].value != label.0 => {
+                        text.sections[0].value = label.0.clone();
+                    }
+                    _ => {}
+                }
+            }
+            if let (
+                Ok(track_children),
+                Widget::Slider {
+                    value, min, max, ..
+                },
+            ) = (track_query.get(*child), widget)
+            {
+                let ratio = if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.
+                };
+                for fill in track_children.iter() {
+                    if let Ok((mut style, mut fill_color)) = fill_query.get_mut(*fill) {
+                        style.size.width = Val::Percent(ratio * 100.);
+                        *fill_color = color.into();
+                    }
+                }
+            }
+        }
+    }
+}
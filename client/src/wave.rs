@@ -0,0 +1,236 @@
+use bevy::{ecs::system::IntoChainSystem, prelude::*, time::FixedTimestep};
+
+use crate::{
+    assets::EnemyAssets,
+    components::{Dead, Enemy, Player},
+    enemy::{spawn_enemy, EnemyArchetype},
+    game::{run_if_in_game, MAP_DOWN_BOUND, MAP_LEFT_BOUND, MAP_RIGHT_BOUND, MAP_UP_BOUND},
+    GameState, TIME_STEP,
+};
+
+pub struct WavePlugin;
+
+// IN SECONDS
+pub const WAVE_INTERMISSION: f32 = 3.0;
+pub const WAVE_TIMEOUT: f32 = 45.0;
+// Enemies of the same group are spread out by this much around their spawn point.
+const SPAWN_SPREAD: f32 = 16.;
+
+/*
+* Whether the director is waiting between waves or running one.
+*/
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WaveState {
+    Intermission,
+    Active,
+}
+
+/*
+* Current progress through the waves, read this to display wave info.
+*/
+pub struct WaveDirector {
+    // 0 until the first wave starts.
+    pub wave: u32,
+    pub state: WaveState,
+    // Counts down the intermission or the wave timeout depending on the state.
+    pub timer: Timer,
+    pub living_enemies: usize,
+}
+
+impl Default for WaveDirector {
+    fn default() -> WaveDirector {
+        WaveDirector {
+            wave: 0,
+            state: WaveState::Intermission,
+            timer: Timer::from_seconds(WAVE_INTERMISSION, false),
+            living_enemies: 0,
+        }
+    }
+}
+
+pub struct WaveDefinition {
+    pub groups: Vec<(EnemyArchetype, u32)>,
+}
+
+/*
+* What each wave spawns and where. Waves past the end of the list repeat the
+* last one with one more enemy per group for every extra wave.
+*/
+pub struct WaveConfig {
+    pub spawn_points: Vec<Vec2>,
+    pub intermission: f32,
+    pub timeout: f32,
+    pub waves: Vec<WaveDefinition>,
+}
+
+impl Default for WaveConfig {
+    fn default() -> WaveConfig {
+        WaveConfig {
+            spawn_points: vec![
+                Vec2::new(MAP_LEFT_BOUND + 20., MAP_UP_BOUND - 20.),
+                Vec2::new(MAP_RIGHT_BOUND - 20., MAP_UP_BOUND - 20.),
+                Vec2::new(MAP_LEFT_BOUND + 20., MAP_DOWN_BOUND + 20.),
+                Vec2::new(MAP_RIGHT_BOUND - 20., MAP_DOWN_BOUND + 20.),
+                Vec2::new(0., MAP_UP_BOUND - 20.),
+                Vec2::new(0., MAP_DOWN_BOUND + 20.),
+            ],
+            intermission: WAVE_INTERMISSION,
+            timeout: WAVE_TIMEOUT,
+            waves: vec![
+                WaveDefinition {
+                    groups: vec![(EnemyArchetype::Chaser, 3)],
+                },
+                WaveDefinition {
+                    groups: vec![
+                        (EnemyArchetype::Chaser, 2),
+                        (EnemyArchetype::KitingArcher, 2),
+                    ],
+                },
+                WaveDefinition {
+                    groups: vec![(EnemyArchetype::Chaser, 3), (EnemyArchetype::Turret, 2)],
+                },
+                WaveDefinition {
+                    groups: vec![
+                        (EnemyArchetype::Chaser, 3),
+                        (EnemyArchetype::KitingArcher, 3),
+                        (EnemyArchetype::Turret, 2),
+                    ],
+                },
+            ],
+        }
+    }
+}
+
+impl WaveConfig {
+    // Groups to spawn for a wave, waves start at 1.
+    pub fn groups_for_wave(&self, wave: u32) -> Vec<(EnemyArchetype, u32)> {
+        let index = (wave as usize).saturating_sub(1);
+        match self.waves.get(index) {
+            Some(definition) => definition.groups.clone(),
+            None => match self.waves.last() {
+                Some(last) => {
+                    let extra = (index + 1 - self.waves.len()) as u32;
+                    last.groups
+                        .iter()
+                        .map(|(archetype, count)| (*archetype, count + extra))
+                        .collect()
+                }
+                None => Vec::new(),
+            },
+        }
+    }
+}
+
+pub struct WaveStartedEvent {
+    pub wave: u32,
+}
+
+pub struct WaveClearedEvent {
+    pub wave: u32,
+}
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveConfig>()
+            .init_resource::<WaveDirector>()
+            .add_event::<WaveStartedEvent>()
+            .add_event::<WaveClearedEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(reset_waves))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64).chain(run_if_in_game))
+                    .with_system(run_waves),
+            );
+    }
+}
+
+fn reset_waves(mut director: ResMut<WaveDirector>, config: Res<WaveConfig>) {
+    *director = WaveDirector {
+        timer: Timer::from_seconds(config.intermission, false),
+        ..default()
+    };
+}
+
+fn run_waves(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    config: Res<WaveConfig>,
+    enemy_assets: Res<EnemyAssets>,
+    enemy_query: Query<Entity, (With<Enemy>, Without<Dead>)>,
+    player_query: Query<&Transform, With<Player>>,
+    mut started_events: EventWriter<WaveStartedEvent>,
+    mut cleared_events: EventWriter<WaveClearedEvent>,
+    time: Res<Time>,
+) {
+    director.living_enemies = enemy_query.iter().count();
+    director.timer.tick(time.delta());
+    match director.state {
+        WaveState::Intermission => {
+            if !director.timer.finished() {
+                return;
+            }
+        }
+        WaveState::Active => {
+            if director.living_enemies == 0 {
+                cleared_events.send(WaveClearedEvent {
+                    wave: director.wave,
+                });
+                director.state = WaveState::Intermission;
+                director.timer = Timer::from_seconds(config.intermission, false);
+                return;
+            }
+            // Timed out, the next wave joins whoever is still alive.
+            if !director.timer.finished() {
+                return;
+            }
+        }
+    }
+    director.wave += 1;
+    director.state = WaveState::Active;
+    director.timer = Timer::from_seconds(config.timeout, false);
+
+    // Use the spawn points furthest from the player first.
+    let player_position = match player_query.get_single() {
+        Ok(player_transform) => player_transform.translation.truncate(),
+        Err(_) => Vec2::ZERO,
+    };
+    let mut spawn_points: Vec<Vec2> = config
+        .spawn_points
+        .iter()
+        .map(|point| {
+            Vec2::new(
+                point.x.clamp(MAP_LEFT_BOUND, MAP_RIGHT_BOUND),
+                point.y.clamp(MAP_DOWN_BOUND, MAP_UP_BOUND),
+            )
+        })
+        .collect();
+    if spawn_points.is_empty() {
+        spawn_points.push(Vec2::ZERO);
+    }
+    spawn_points.sort_by(|a, b| {
+        b.distance_squared(player_position)
+            .partial_cmp(&a.distance_squared(player_position))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    for (group_index, (archetype, count)) in
+        config.groups_for_wave(director.wave).iter().enumerate()
+    {
+        let spawn_point = spawn_points[group_index % spawn_points.len()];
+        for i in 0..*count {
+            let offset = Vec2::new(
+                (i % 3) as f32 - 1.,
+                (i / 3) as f32 * if spawn_point.y > 0. { -1. } else { 1. },
+            ) * SPAWN_SPREAD;
+            spawn_enemy(
+                &mut commands,
+                *archetype,
+                spawn_point + offset,
+                &enemy_assets,
+            );
+        }
+    }
+    started_events.send(WaveStartedEvent {
+        wave: director.wave,
+    });
+}
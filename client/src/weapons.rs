@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+pub struct WeaponPlugin;
+
+/*
+* Layout of a `.weapon.ron` file. Paths are relative to the assets folder.
+*/
+#[derive(Deserialize)]
+struct WeaponFile {
+    projectile_speed: f32,
+    // IN SECONDS
+    fire_cooldown: f32,
+    damage: f32,
+    // Total cone in degrees that shots are randomly spread over.
+    #[serde(default)]
+    spread: f32,
+    // Chance from 0 to 1 for a shot to be a critical hit, dealing `damage * crit_multiplier`.
+    #[serde(default)]
+    crit_chance: f32,
+    #[serde(default = "default_crit_multiplier")]
+    crit_multiplier: f32,
+    // Arrow sprite pointing right, it is rotated to the direction of flight.
+    sprite: String,
+    sound: String,
+}
+
+fn default_crit_multiplier() -> f32 {
+    2.
+}
+
+impl WeaponFile {
+    // Timers and the spread cone panic on values that aren't finite.
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        for (field, value) in [
+            ("projectile_speed", self.projectile_speed),
+            ("fire_cooldown", self.fire_cooldown),
+        ] {
+            if !value.is_finite() || value <= 0. {
+                anyhow::bail!("{} is {}, it must be above 0", field, value);
+            }
+        }
+        for (field, value) in [
+            ("damage", self.damage),
+            ("spread", self.spread),
+            ("crit_multiplier", self.crit_multiplier),
+        ] {
+            if !value.is_finite() || value < 0. {
+                anyhow::bail!("{} is {}, it can't be negative", field, value);
+            }
+        }
+        if !(0. ..=1.).contains(&self.crit_chance) {
+            anyhow::bail!(
+                "crit_chance is {}, it must be between 0 and 1",
+                self.crit_chance
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(TypeUuid)]
+#[uuid = "ec0814aa-d5a9-413c-9d06-8c484a47167b"]
+pub struct WeaponDefinition {
+    pub projectile_speed: f32,
+    pub fire_cooldown: f32,
+    pub damage: f32,
+    pub spread: f32,
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
+    pub sprite: Handle<Image>,
+    pub sound: Handle<AudioSource>,
+}
+
+#[derive(Default)]
+pub struct WeaponLoader;
+
+impl AssetLoader for WeaponLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let file: WeaponFile = ron::de::from_bytes(bytes)?;
+            file.validate()?;
+            let sprite_path: AssetPath<'static> = AssetPath::new(PathBuf::from(&file.sprite), None);
+            let sound_path: AssetPath<'static> = AssetPath::new(PathBuf::from(&file.sound), None);
+            let weapon = WeaponDefinition {
+                projectile_speed: file.projectile_speed,
+                fire_cooldown: file.fire_cooldown,
+                damage: file.damage,
+                spread: file.spread,
+                crit_chance: file.crit_chance,
+                crit_multiplier: file.crit_multiplier,
+                sprite: load_context.get_handle(sprite_path.clone()),
+                sound: load_context.get_handle(sound_path.clone()),
+            };
+            load_context.set_default_asset(
+                LoadedAsset::new(weapon).with_dependencies(vec![sprite_path, sound_path]),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["weapon.ron"]
+    }
+}
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WeaponDefinition>()
+            .init_asset_loader::<WeaponLoader>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BAD_VALUES: [f32; 4] = [-1., f32::NAN, f32::INFINITY, f32::NEG_INFINITY];
+
+    fn weapon_file() -> WeaponFile {
+        ron::de::from_str(include_str!("../assets/game/weapons/turret_bow.weapon.ron")).unwrap()
+    }
+
+    #[test]
+    fn accepts_the_shipped_weapons() {
+        for text in [
+            include_str!("../assets/game/weapons/archer_bow.weapon.ron"),
+            include_str!("../assets/game/weapons/chaser_bow.weapon.ron"),
+            include_str!("../assets/game/weapons/kiting_archer_bow.weapon.ron"),
+            include_str!("../assets/game/weapons/turret_bow.weapon.ron"),
+        ] {
+            let file: WeaponFile = ron::de::from_str(text).unwrap();
+            assert!(file.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn rejects_cooldowns_and_speeds_that_arent_above_0() {
+        for value in BAD_VALUES.into_iter().chain([0.]) {
+            let mut file = weapon_file();
+            file.fire_cooldown = value;
+            assert!(file.validate().is_err(), "fire_cooldown {}", value);
+            let mut file = weapon_file();
+            file.projectile_speed = value;
+            assert!(file.validate().is_err(), "projectile_speed {}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_negative_or_infinite_damage_spread_and_multiplier() {
+        for value in BAD_VALUES {
+            let mut file = weapon_file();
+            file.damage = value;
+            assert!(file.validate().is_err(), "damage {}", value);
+            let mut file = weapon_file();
+            file.spread = value;
+            assert!(file.validate().is_err(), "spread {}", value);
+            let mut file = weapon_file();
+            file.crit_multiplier = value;
+            assert!(file.validate().is_err(), "crit_multiplier {}", value);
+        }
+        let mut file = weapon_file();
+        file.damage = 0.;
+        file.spread = 0.;
+        file.crit_multiplier = 0.;
+        assert!(file.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_crit_chances_outside_0_to_1() {
+        for value in [-0.1, 1.5, f32::NAN] {
+            let mut file = weapon_file();
+            file.crit_chance = value;
+            assert!(file.validate().is_err(), "crit_chance {}", value);
+        }
+        let mut file = weapon_file();
+        file.crit_chance = 1.;
+        assert!(file.validate().is_ok());
+    }
+}